@@ -0,0 +1,64 @@
+//! Compares the magic bitboard lookups with the ray walks they replaced.
+//!
+//!     cargo bench --no-default-features --bench attacks
+
+use std::time::Instant;
+
+use chess_rs::bitboard::{self, Bitboard};
+use chess_rs::Square;
+
+const ROUNDS: usize = 200;
+
+/* Occupancies to look the attacks up with, about a third of the board
+ * full, from a fixed xorshift sequence.
+ */
+fn occupancies() -> Vec<Bitboard> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    (0..1000).map(|_| next() & next()).collect()
+}
+
+/* Runs `attacks` for every square and occupancy ROUNDS times and prints
+ * how long one call took on average.
+ */
+fn bench(name: &str, occupancies: &[Bitboard], attacks: fn(Square, Bitboard) -> Bitboard) {
+    let start = Instant::now();
+    let mut seen: Bitboard = 0;
+
+    for _ in 0..ROUNDS {
+        for occupied in occupancies {
+            for sq in 0..64 {
+                seen = seen.wrapping_add(attacks(Square::from_index(sq), *occupied));
+            }
+        }
+    }
+
+    let calls = (ROUNDS * occupancies.len() * 64) as f64;
+    let elapsed = start.elapsed().as_secs_f64();
+
+    // Printing `seen` keeps the calls from being optimised away
+    println!(
+        "{:<8} {:>6.2} ns/call  ({:x})",
+        name,
+        elapsed * 1e9 / calls,
+        seen
+    );
+}
+
+fn main() {
+    let occupancies = occupancies();
+
+    // Build the magic tables before timing anything
+    bitboard::rook_attacks(Square::from_index(0), 0);
+
+    bench("bishop", &occupancies, bitboard::bishop_attacks);
+    bench("  rays", &occupancies, bitboard::bishop_rays);
+    bench("rook", &occupancies, bitboard::rook_attacks);
+    bench("  rays", &occupancies, bitboard::rook_rays);
+}
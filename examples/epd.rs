@@ -0,0 +1,145 @@
+//! Runs a test suite from an EPD file: every position is searched and the
+//! move found is checked against its `bm` (best move) and `am` (avoid
+//! move) operations. Suites like STS that give points for several moves in
+//! `c0`, as in `c0 "f5=10, Bf2=3";`, are scored as well.
+//!
+//!     cargo run --release --no-default-features --example epd -- wac.epd [--depth N | --time MS] [--write out.epd]
+//!
+//! By default every position gets a second. With `--write` the positions
+//! are written out again along with what the search found, as `acd`,
+//! `acn`, `ce` and `pm` operations.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use chess_rs::search::{self, Limit};
+use chess_rs::Epd;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+
+    let path = match args.get(1) {
+        Some(path) if !path.starts_with("--") => path,
+        _ => {
+            eprintln!("Usage: epd <file> [--depth N | --time MS] [--write <file>]");
+            std::process::exit(1);
+        }
+    };
+
+    let limit = match (option("--depth"), option("--time")) {
+        (Some(depth), _) => Limit::Depth(depth.parse().expect("--depth needs a number")),
+        (None, Some(ms)) => Limit::Time(Duration::from_millis(
+            ms.parse().expect("--time needs a number of milliseconds"),
+        )),
+        (None, None) => Limit::Time(Duration::from_secs(1)),
+    };
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let (mut passed, mut failed, mut errors) = (0, 0, 0);
+    let (mut points, mut max_points) = (0, 0);
+    let mut nodes = 0;
+    let mut written = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut epd: Epd = match line.parse() {
+            Ok(epd) => epd,
+            Err(e) => {
+                println!("line {}: {}", i + 1, e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let (best, avoid) = match (epd.moves("bm"), epd.moves("am")) {
+            (Ok(best), Ok(avoid)) => (best, avoid),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("line {}: {} in bm or am", i + 1, e);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let name = epd
+            .id()
+            .map_or_else(|| format!("line {}", i + 1), String::from);
+        let result = search::search(&epd.position, limit);
+        nodes += result.nodes;
+
+        let found = match result.best_move {
+            Some(mv) => mv,
+            None => {
+                println!("{}: no legal moves", name);
+                errors += 1;
+                continue;
+            }
+        };
+
+        let san = epd.position.san(&found);
+        let pass = (best.is_empty() || best.contains(&found)) && !avoid.contains(&found);
+
+        if best.is_empty() && avoid.is_empty() {
+            println!("{}: {} (nothing to check)", name, san);
+        } else if pass {
+            passed += 1;
+            println!("{}: pass {}", name, san);
+        } else {
+            failed += 1;
+            println!("{}: FAIL {}", name, san);
+        }
+
+        if let Some(scores) = epd.move_points() {
+            max_points += scores.iter().map(|(_, p)| *p).max().unwrap_or(0);
+            points += scores
+                .iter()
+                .find(|(mv, _)| epd.position.parse_san(mv) == Ok(found))
+                .map_or(0, |(_, p)| *p);
+        }
+
+        epd.set_operation("acd", vec![result.depth.to_string()]);
+        epd.set_operation("acn", vec![result.nodes.to_string()]);
+        epd.set_operation("ce", vec![result.score.to_string()]);
+        epd.set_operation("pm", vec![san]);
+        written.push(epd.to_string());
+    }
+
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!();
+    println!("Passed: {}/{}", passed, passed + failed);
+    if errors > 0 {
+        println!("Errors: {}", errors);
+    }
+    if max_points > 0 {
+        println!("Score: {}/{}", points, max_points);
+    }
+    println!("Nodes: {}", nodes);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed,
+        nodes as f64 / elapsed
+    );
+
+    if let Some(out) = option("--write") {
+        if let Err(e) = fs::write(out, written.join("\n") + "\n") {
+            eprintln!("{}: {}", out, e);
+            std::process::exit(1);
+        }
+    }
+}
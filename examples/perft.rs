@@ -0,0 +1,30 @@
+//! Runs perft from the starting position and prints the node count under
+//! every first move.
+//!
+//!     cargo run --release --no-default-features --example perft -- 5
+
+use std::time::Instant;
+
+use chess_rs::Position;
+
+fn main() {
+    let depth = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse().ok())
+        .unwrap_or(5);
+
+    let position = Position::new();
+    let start = Instant::now();
+    let mut total = 0;
+
+    for (mv, nodes) in position.divide(depth) {
+        println!("{}: {}", mv, nodes);
+        total += nodes;
+    }
+
+    let elapsed = start.elapsed();
+
+    println!();
+    println!("Nodes: {}", total);
+    println!("Time: {:.3}s", elapsed.as_secs_f64());
+}
@@ -0,0 +1,419 @@
+//! 64-bit boards with one bit per square, and the attack tables built on
+//! top of them.
+//!
+//! Bit `n` stands for the [`Square`](../square/struct.Square.html) with
+//! index `n`, so bit 0 is a1, bit 7 is h1 and bit 63 is h8.
+//!
+//! Bishop and rook attacks are looked up in magic bitboard tables, which
+//! are filled in the first time they are needed. The ray walks the tables
+//! are built from are still around as [`bishop_rays`](fn.bishop_rays.html)
+//! and [`rook_rays`](fn.rook_rays.html).
+
+use std::sync::OnceLock;
+
+use crate::piece::Colour;
+use crate::square::Square;
+
+/// A set of squares.
+pub type Bitboard = u64;
+
+pub const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+pub const FILE_H: Bitboard = FILE_A << 7;
+pub const RANK_1: Bitboard = 0xFF;
+pub const RANK_8: Bitboard = RANK_1 << 56;
+
+/// The board with only `sq` set.
+pub const fn bit(sq: Square) -> Bitboard {
+    1 << sq.index()
+}
+
+/// Removes the lowest square from `bb` and returns it. `bb` must not be
+/// empty.
+pub fn pop_lsb(bb: &mut Bitboard) -> Square {
+    let sq = Square::from_index(bb.trailing_zeros() as usize);
+    *bb &= *bb - 1;
+    sq
+}
+
+/// Iterates over the squares of a bitboard, lowest first.
+pub struct Squares(pub Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(pop_lsb(&mut self.0))
+        }
+    }
+}
+
+/* Builds a table of the squares reachable with single steps of (dx, dy),
+ * with dy counted towards the 8th rank.
+ */
+const fn step_table(steps: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut sq = 0;
+
+    while sq < 64 {
+        let x = (sq % 8) as i8;
+        let y = (sq / 8) as i8;
+        let mut i = 0;
+
+        while i < steps.len() {
+            let (dx, dy) = steps[i];
+            let (t_x, t_y) = (x + dx, y + dy);
+
+            if t_x >= 0 && t_x < 8 && t_y >= 0 && t_y < 8 {
+                table[sq] |= 1 << (t_y * 8 + t_x);
+            }
+
+            i += 1;
+        }
+
+        sq += 1;
+    }
+
+    table
+}
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = step_table(&[
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+    (-2, 1),
+    (-1, 2),
+    (1, 2),
+    (2, 1),
+]);
+
+const KING_ATTACKS: [Bitboard; 64] = step_table(&[
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+]);
+
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    step_table(&[(-1, 1), (1, 1)]),
+    step_table(&[(-1, -1), (1, -1)]),
+];
+
+/* The eight ray directions. The first four run towards higher squares,
+ * the last four towards lower ones, which decides whether the nearest
+ * blocker is the lowest or the highest bit on the ray.
+ */
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 0),
+    (1, 1),
+    (-1, 1),
+    (0, -1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+];
+
+/* Builds the table of full rays (up to the edge of the board) from every
+ * square in every direction.
+ */
+const fn ray_table() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut dir = 0;
+
+    while dir < 8 {
+        let (dx, dy) = DIRECTIONS[dir];
+        let mut sq = 0;
+
+        while sq < 64 {
+            let mut x = (sq % 8) as i8 + dx;
+            let mut y = (sq / 8) as i8 + dy;
+
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                table[dir][sq] |= 1 << (y * 8 + x);
+                x += dx;
+                y += dy;
+            }
+
+            sq += 1;
+        }
+
+        dir += 1;
+    }
+
+    table
+}
+
+const RAYS: [[Bitboard; 64]; 8] = ray_table();
+
+/* Builds two tables indexed by a pair of squares on a shared rank, file or
+ * diagonal: the squares strictly between them, and the whole line through
+ * them from edge to edge. Pairs that share no line get empty boards.
+ */
+const fn line_tables() -> [[[Bitboard; 64]; 64]; 2] {
+    let mut tables = [[[0; 64]; 64]; 2];
+    let mut a = 0;
+
+    while a < 64 {
+        let mut dir = 0;
+
+        while dir < 8 {
+            let ray = RAYS[dir][a];
+            let back = RAYS[(dir + 4) % 8][a];
+            let mut b = 0;
+
+            while b < 64 {
+                if ray & (1 << b) != 0 {
+                    tables[0][a][b] = ray & !RAYS[dir][b] & !(1 << b);
+                    tables[1][a][b] = ray | back | (1 << a);
+                }
+
+                b += 1;
+            }
+
+            dir += 1;
+        }
+
+        a += 1;
+    }
+
+    tables
+}
+
+static LINES: [[[Bitboard; 64]; 64]; 2] = line_tables();
+
+/* The squares a slider on `sq` sees in direction `dir`, up to and
+ * including the first occupied square.
+ */
+fn ray_attacks(dir: usize, sq: Square, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[dir][sq.index()];
+    let blockers = ray & occupied;
+
+    if blockers == 0 {
+        return ray;
+    }
+
+    let blocker = if dir < 4 {
+        blockers.trailing_zeros() as usize
+    } else {
+        63 - blockers.leading_zeros() as usize
+    };
+
+    ray ^ RAYS[dir][blocker]
+}
+
+/// The squares a knight on `sq` attacks.
+pub fn knight_attacks(sq: Square) -> Bitboard {
+    KNIGHT_ATTACKS[sq.index()]
+}
+
+/// The squares a king on `sq` attacks.
+pub fn king_attacks(sq: Square) -> Bitboard {
+    KING_ATTACKS[sq.index()]
+}
+
+/// The squares a pawn of `colour` on `sq` attacks.
+pub fn pawn_attacks(colour: Colour, sq: Square) -> Bitboard {
+    PAWN_ATTACKS[colour.index()][sq.index()]
+}
+
+/// The squares strictly between `a` and `b`, or none if they aren't on
+/// the same rank, file or diagonal.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    LINES[0][a.index()][b.index()]
+}
+
+/// The whole rank, file or diagonal through `a` and `b`, or none if they
+/// aren't on one.
+pub fn line(a: Square, b: Square) -> Bitboard {
+    LINES[1][a.index()][b.index()]
+}
+
+/// The squares a bishop on `sq` attacks with the given squares occupied.
+pub fn bishop_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    let magics = magics();
+    magics.table[magics.bishop[sq.index()].index(occupied)]
+}
+
+/// The squares a rook on `sq` attacks with the given squares occupied.
+pub fn rook_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    let magics = magics();
+    magics.table[magics.rook[sq.index()].index(occupied)]
+}
+
+/// The squares a queen on `sq` attacks with the given squares occupied.
+pub fn queen_attacks(sq: Square, occupied: Bitboard) -> Bitboard {
+    bishop_attacks(sq, occupied) | rook_attacks(sq, occupied)
+}
+
+/// Same as [`bishop_attacks`](fn.bishop_attacks.html), worked out by
+/// walking the four diagonals instead of looking the answer up.
+pub fn bishop_rays(sq: Square, occupied: Bitboard) -> Bitboard {
+    ray_attacks(2, sq, occupied)
+        | ray_attacks(3, sq, occupied)
+        | ray_attacks(6, sq, occupied)
+        | ray_attacks(7, sq, occupied)
+}
+
+/// Same as [`rook_attacks`](fn.rook_attacks.html), worked out by walking
+/// the four lines instead of looking the answer up.
+pub fn rook_rays(sq: Square, occupied: Bitboard) -> Bitboard {
+    ray_attacks(0, sq, occupied)
+        | ray_attacks(1, sq, occupied)
+        | ray_attacks(4, sq, occupied)
+        | ray_attacks(5, sq, occupied)
+}
+
+/* The lookup for one slider on one square. Only the squares in `mask` can
+ * block the slider (the last square of a ray never blocks anything), and
+ * multiplying them by `magic` gathers them into the top `64 - shift` bits
+ * without two blocker sets that need different answers colliding.
+ */
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    /* Where the attacks for the blockers in `occupied` are in the table.
+     */
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+/* The magics of every square and the attack table they index into, which
+ * the bishops and rooks share.
+ */
+struct Magics {
+    bishop: Vec<Magic>,
+    rook: Vec<Magic>,
+    table: Vec<Bitboard>,
+}
+
+static MAGICS: OnceLock<Magics> = OnceLock::new();
+
+fn magics() -> &'static Magics {
+    MAGICS.get_or_init(|| {
+        let mut table = Vec::with_capacity(107_648);
+        let edges = FILE_A | FILE_H | RANK_1 | RANK_8;
+
+        let bishop = (0..64)
+            .map(|sq| {
+                let mask = bishop_rays(Square::from_index(sq), 0) & !edges;
+
+                fill_table(sq, mask, BISHOP_MAGICS[sq], bishop_rays, &mut table)
+            })
+            .collect();
+
+        // The edges only end the rays that run into them
+        let rook = (0..64)
+            .map(|sq| {
+                let mask = (RAYS[0][sq] & !RANK_8)
+                    | (RAYS[1][sq] & !FILE_H)
+                    | (RAYS[4][sq] & !RANK_1)
+                    | (RAYS[5][sq] & !FILE_A);
+
+                fill_table(sq, mask, ROOK_MAGICS[sq], rook_rays, &mut table)
+            })
+            .collect();
+
+        Magics {
+            bishop,
+            rook,
+            table,
+        }
+    })
+}
+
+/* Appends the slots for a slider on `sq` to `table`, working out the
+ * attacks for every subset of `mask` with `rays`.
+ */
+fn fill_table(
+    sq: usize,
+    mask: Bitboard,
+    magic: u64,
+    rays: fn(Square, Bitboard) -> Bitboard,
+    table: &mut Vec<Bitboard>,
+) -> Magic {
+    let bits = mask.count_ones();
+    let magic = Magic {
+        mask,
+        magic,
+        shift: 64 - bits,
+        offset: table.len(),
+    };
+
+    table.resize(table.len() + (1 << bits), 0);
+
+    // Walks through every subset of the mask, starting with the empty one
+    let mut subset: Bitboard = 0;
+
+    loop {
+        let attacks = rays(Square::from_index(sq), subset);
+        let slot = &mut table[magic.index(subset)];
+
+        debug_assert!(*slot == 0 || *slot == attacks, "bad magic for {}", sq);
+        *slot = attacks;
+
+        subset = subset.wrapping_sub(mask) & mask;
+
+        if subset == 0 {
+            break;
+        }
+    }
+
+    magic
+}
+
+/* Magic numbers for every square, a1 first. They were found by trying
+ * random numbers with few bits set until one had no bad collisions.
+ */
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0020428400408200, 0x2008010104210004, 0x02D0009200480190, 0x0018158B00010100,
+    0x02C4042132048008, 0x020082202000C221, 0x4000421050080009, 0x0210140202022020,
+    0x00C0101410042248, 0x0405204800D48080, 0x3800C89200420002, 0x180844124A020440,
+    0x04403410A8002221, 0x4040209004200400, 0x084004020202A204, 0x3010002104022000,
+    0x00200240A9110900, 0x2302800404080210, 0x0204188800240010, 0x8048000C01401200,
+    0x120C001A11040900, 0x0000401200500440, 0x00004040840420A0, 0x0020930822880804,
+    0x4044401090900161, 0x0034100015210804, 0x8004100009010120, 0x48C8080000820500,
+    0x0080848004002000, 0x0801004012005044, 0x000080902C040400, 0x0004009005004100,
+    0x0B103010048A0200, 0x8004100203181A00, 0x0800140200100080, 0x8401010800910040,
+    0x0840010011290040, 0x40100214202E1000, 0x0842040040010840, 0x0028010040010860,
+    0x00080202A2051000, 0x4200841008084204, 0x0021120110000D02, 0x48C1004208000084,
+    0x0010088100414400, 0x0021101000420580, 0x0010040558401410, 0x200C0C82A1050205,
+    0x0011108820088000, 0x0001011910120402, 0x1580008608091248, 0x8010018020880C02,
+    0x20A1101032088480, 0x0080100408082800, 0x28100401140401C0, 0x8002102200930012,
+    0x4001040082080200, 0x082200A498081808, 0x000508610080D003, 0x0052020044842402,
+    0x4800A00140C84840, 0x5000000848080820, 0x0101086004240040, 0x0028280808005014,
+];
+
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x008000908064C000, 0x0040200040001000, 0x0180100080A0010A, 0x8880041000800800,
+    0x1200100201200804, 0x0200020004011008, 0x2180010000800600, 0x0200005088210204,
+    0x0400800040008021, 0x0400400020005000, 0x8240801000200080, 0x8611001004200900,
+    0x008180800C001800, 0x0100800200800400, 0x0A02000102000408, 0x8020802300104280,
+    0x0080004000402000, 0xE010104000402000, 0x0800808010002000, 0xA280210008100100,
+    0x0001818014000800, 0xA002010100080400, 0x0080240001020870, 0x0001020004048845,
+    0x0081826280004004, 0x2020810900284000, 0x0200100080802000, 0x0200080080100080,
+    0x8083080100100500, 0x4406000901000400, 0x0005020080800100, 0x0090204200008114,
+    0x0010400094800420, 0x0900804000802002, 0x0201001841002000, 0x4100080080801000,
+    0x4540040080800800, 0x0002001004040020, 0x0281195814001002, 0x1240800040800100,
+    0x0880042000524004, 0x02C080410206002C, 0x0801200241050010, 0x8400080010008080,
+    0x0008000500090010, 0x0082009084020008, 0x4012000108020004, 0x9000104D08860004,
+    0x2004204114800100, 0x0148802112400300, 0x0202842000100880, 0x001B080080900080,
+    0x001A002008100600, 0x0004008004020080, 0x5181000600040300, 0x0000044401128A00,
+    0x8044110480002441, 0x2008110084402202, 0x90806005090010C1, 0x000420310A004A42,
+    0x0023001004020801, 0x0882001008040102, 0x000230088118020C, 0x0000019025040042,
+];
@@ -0,0 +1,153 @@
+//! Opening books in the Polyglot `.bin` format.
+//!
+//! A book is a list of 16-byte entries sorted by the Zobrist key of the
+//! position they're for, each holding a move and a weight saying how often
+//! it should be played. The keys are the Polyglot ones that
+//! [`Position::hash`](../position/struct.Position.html#method.hash) already
+//! works out.
+
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::Path;
+
+use crate::moves::Move;
+use crate::piece::PieceKind;
+use crate::position::Position;
+use crate::square::Square;
+
+/// How a move is picked when a position has several book moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Selection {
+    /// Always the move with the highest weight.
+    Best,
+    /// A random move, each one as likely as its weight says.
+    Weighted,
+}
+
+/// An opening book read into memory.
+#[derive(Clone, Debug)]
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+/* A single entry of the book, with the move still encoded.
+ */
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    key: u64,
+    mv: u16,
+    weight: u16,
+}
+
+impl Book {
+    /// Reads the book at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Reads a book from the contents of a `.bin` file. Fails if they
+    /// aren't made of whole entries.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        if bytes.len() % 16 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Polyglot book",
+            ));
+        }
+
+        let entries = bytes
+            .chunks_exact(16)
+            .map(|chunk| Entry {
+                key: u64::from_be_bytes([
+                    chunk[0], chunk[1], chunk[2], chunk[3], chunk[4], chunk[5], chunk[6], chunk[7],
+                ]),
+                mv: u16::from_be_bytes([chunk[8], chunk[9]]),
+                weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+            })
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    /// The number of entries in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the book has no entries at all.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The legal book moves of `position` with their weights, the highest
+    /// weight first.
+    pub fn moves(&self, position: &Position) -> Vec<(Move, u16)> {
+        let key = position.hash();
+        let first = self.entries.partition_point(|entry| entry.key < key);
+        let legal = position.legal_moves();
+
+        let mut moves: Vec<(Move, u16)> = self.entries[first..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| {
+                legal
+                    .iter()
+                    .find(|mv| decoded_as(position, mv, entry.mv))
+                    .map(|mv| (*mv, entry.weight))
+            })
+            .collect();
+
+        moves.sort_by_key(|(_, weight)| std::cmp::Reverse(*weight));
+        moves
+    }
+
+    /// Picks one of the book moves of `position`, or `None` if it isn't in
+    /// the book.
+    pub fn choose(&self, position: &Position, selection: Selection) -> Option<Move> {
+        let moves = self.moves(position);
+        let total: u64 = moves.iter().map(|(_, weight)| *weight as u64).sum();
+
+        if selection == Selection::Best || total == 0 {
+            return moves.first().map(|(mv, _)| *mv);
+        }
+
+        let mut pick = RandomState::new().build_hasher().finish() % total;
+
+        for (mv, weight) in moves {
+            if pick < weight as u64 {
+                return Some(mv);
+            }
+
+            pick -= weight as u64;
+        }
+
+        None
+    }
+}
+
+/* Whether `mv` is the move encoded as `code`: the file and rank of the
+ * destination in the lowest six bits, then those of the origin, then the
+ * promotion. Castling is encoded as the king taking its own rook.
+ */
+fn decoded_as(position: &Position, mv: &Move, code: u16) -> bool {
+    let square = |bits: u16| Square::new((bits & 7) as u8, (bits >> 3 & 7) as u8);
+    let to = square(code);
+    let from = square(code >> 6);
+
+    let promotion = match code >> 12 & 7 {
+        1 => Some(PieceKind::Knight),
+        2 => Some(PieceKind::Bishop),
+        3 => Some(PieceKind::Rook),
+        4 => Some(PieceKind::Queen),
+        _ => None,
+    };
+
+    let lands = match position.castling_rook(mv) {
+        Some(rook) => rook == to,
+        None => mv.to == to,
+    };
+
+    mv.from == from && lands && mv.promotion == promotion
+}
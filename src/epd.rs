@@ -0,0 +1,264 @@
+//! Extended Position Description, the line-per-position format test suites
+//! like WAC and STS come in.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::moves::Move;
+use crate::position::{FenError, Position};
+use crate::san::ParseSanError;
+
+/// A position with the operations that describe it, like `bm Qxf7+;` for
+/// the best move or `id "WAC.001";` for its name.
+#[derive(Clone)]
+pub struct Epd {
+    pub position: Position,
+    /// The operations in the order they were written, each an opcode with
+    /// its operands. Quotes around string operands aren't kept.
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+/// Returned for a line that isn't a valid EPD record.
+#[derive(Clone, PartialEq, Debug)]
+pub enum EpdError {
+    /// The four position fields don't make up a valid position.
+    Fen(FenError),
+    /// An operation without an opcode, with a string that's never closed,
+    /// or with a counter that isn't a number.
+    Operation,
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Fen(e) => write!(f, "{}", e),
+            Self::Operation => write!(f, "malformed operation"),
+        }
+    }
+}
+
+impl Error for EpdError {}
+
+impl Epd {
+    /// A record of `position` without any operations.
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            operations: Vec::new(),
+        }
+    }
+
+    /// The operands of the first operation with `opcode`, if there is one.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Sets the operands of `opcode`, replacing the operation if it's
+    /// already there or adding it at the end.
+    pub fn set_operation(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(op, _)| op == opcode) {
+            Some((_, old)) => *old = operands,
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+
+    /// The name of the position from its `id` operation.
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+            .and_then(|operands| operands.first())
+            .map(String::as_str)
+    }
+
+    /// The moves given by `opcode` as SAN, like the best moves of `bm` or
+    /// the moves to avoid of `am`. Empty if there's no such operation.
+    pub fn moves(&self, opcode: &str) -> Result<Vec<Move>, ParseSanError> {
+        self.operation(opcode)
+            .unwrap_or(&[])
+            .iter()
+            .map(|san| self.position.parse_san(san))
+            .collect()
+    }
+
+    /// The number given by `opcode`, like the depth of `acd` or the score
+    /// of `ce`.
+    pub fn number(&self, opcode: &str) -> Option<i64> {
+        self.operation(opcode)?.first()?.parse().ok()
+    }
+
+    /// The points the `c0` operation gives for each move in suites like
+    /// STS, written like `f5=10, Bf2=3` or `e8=Q=10`, or `None` if it
+    /// doesn't hold points.
+    pub fn move_points(&self) -> Option<Vec<(String, u32)>> {
+        let comment = self.operation("c0")?.first()?;
+
+        comment
+            .split(',')
+            .map(|pair| {
+                let (mv, points) = pair.trim().rsplit_once('=')?;
+                Some((mv.to_string(), points.parse().ok()?))
+            })
+            .collect()
+    }
+}
+
+/// Reads an EPD record: the first four fields of a FEN followed by
+/// operations ending in semicolons. The `hmvc` and `fmvn` operations set
+/// the move counters of the position.
+impl FromStr for Epd {
+    type Err = EpdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut fields = Vec::new();
+        let mut rest = s;
+
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let operations = read_operations(rest)?;
+        let counter = |opcode: &str, default: &str| -> Result<String, EpdError> {
+            match operations.iter().find(|(op, _)| op == opcode) {
+                Some((_, operands)) => match operands.first() {
+                    Some(n) if n.parse::<u16>().is_ok() => Ok(n.clone()),
+                    _ => Err(EpdError::Operation),
+                },
+                None => Ok(default.to_string()),
+            }
+        };
+
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            counter("hmvc", "0")?,
+            counter("fmvn", "1")?
+        );
+        let position = Position::from_fen(&fen).map_err(EpdError::Fen)?;
+
+        Ok(Self {
+            position,
+            operations,
+        })
+    }
+}
+
+/// Writes the record back as a single line.
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fen = self.position.to_fen();
+        let fields: Vec<&str> = fen.split(' ').take(4).collect();
+
+        write!(f, "{}", fields.join(" "))?;
+
+        for (opcode, operands) in &self.operations {
+            write!(f, " {}", opcode)?;
+
+            for operand in operands {
+                if needs_quotes(opcode, operand) {
+                    write!(f, " \"{}\"", operand)?;
+                } else {
+                    write!(f, " {}", operand)?;
+                }
+            }
+
+            write!(f, ";")?;
+        }
+
+        Ok(())
+    }
+}
+
+/* Splits the operations after the position fields into opcodes and
+ * operands. The semicolon after the last operation may be left out.
+ */
+fn read_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut chars = text.chars().peekable();
+    let mut operations = Vec::new();
+
+    loop {
+        while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+            chars.next();
+        }
+
+        let mut opcode = String::new();
+
+        while let Some(ch) = chars.peek().copied() {
+            if ch.is_whitespace() || ch == ';' {
+                break;
+            }
+
+            opcode.push(ch);
+            chars.next();
+        }
+
+        if opcode.is_empty() {
+            return match chars.next() {
+                None => Ok(operations),
+                Some(_) => Err(EpdError::Operation),
+            };
+        }
+
+        if !opcode.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+            return Err(EpdError::Operation);
+        }
+
+        let mut operands = Vec::new();
+
+        loop {
+            match chars.next() {
+                None | Some(';') => break,
+                Some(ch) if ch.is_whitespace() => (),
+                Some('"') => {
+                    let mut operand = String::new();
+
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(ch) => operand.push(ch),
+                            None => return Err(EpdError::Operation),
+                        }
+                    }
+
+                    operands.push(operand);
+                }
+                Some(ch) => {
+                    let mut operand = ch.to_string();
+
+                    while let Some(ch) = chars.peek().copied() {
+                        if ch.is_whitespace() || ch == ';' {
+                            break;
+                        }
+
+                        operand.push(ch);
+                        chars.next();
+                    }
+
+                    operands.push(operand);
+                }
+            }
+        }
+
+        operations.push((opcode, operands));
+    }
+}
+
+/* Whether an operand has to be written as a string: names and comments
+ * always are, anything else only if it has spaces or semicolons in it.
+ */
+fn needs_quotes(opcode: &str, operand: &str) -> bool {
+    let comment = opcode.len() == 2
+        && opcode.starts_with('c')
+        && opcode[1..].chars().all(|ch| ch.is_ascii_digit());
+
+    opcode == "id"
+        || comment
+        || operand.is_empty()
+        || operand.contains(|ch: char| ch.is_whitespace() || ch == ';')
+}
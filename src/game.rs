@@ -1,836 +1,316 @@
-use std::collections::HashMap;
+use std::error::Error;
 use std::fmt;
 
-use ggez::{
-    event::{EventHandler, MouseButton},
-    graphics::{self, Color, DrawMode, DrawParam, Image},
-    Context, GameResult,
-};
-
 use std::vec::Vec;
 
-use crate::WIN_SIZE;
-
-#[derive(Clone, Copy)]
-enum Player {
-    White,
-    Black,
-}
-
-impl Player {
-    fn switch(&self) -> Self {
-        match *self {
-            Self::White => Self::Black,
-            Self::Black => Self::White,
-        }
-    }
+use crate::moves::Move;
+use crate::piece::Colour;
+use crate::position::Position;
+
+/// Why a game ended in a draw.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawReason {
+    Stalemate,
+    FiftyMoves,
+    SeventyFiveMoves,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    InsufficientMaterial,
+    TimeoutVsInsufficientMaterial,
 }
 
-impl fmt::Display for Player {
+impl fmt::Display for DrawReason {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let to_print = match *self {
-            Self::White => "White",
-            Self::Black => "Black",
+            Self::Stalemate => "stalemate",
+            Self::FiftyMoves => "the fifty-move rule",
+            Self::SeventyFiveMoves => "the seventy-five-move rule",
+            Self::ThreefoldRepetition => "threefold repetition",
+            Self::FivefoldRepetition => "fivefold repetition",
+            Self::InsufficientMaterial => "insufficient material",
+            Self::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
         };
 
         write!(f, "{}", to_print)
     }
 }
 
-struct Point<T>
-where
-    T: Copy,
-{
-    x: T,
-    y: T,
+/// The result of a finished game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw(DrawReason),
 }
 
-impl<T> Point<T>
-where
-    T: Copy,
-{
-    fn new(x: T, y: T) -> Self {
-        Self { x, y }
+impl Outcome {
+    /// The outcome of a game that `plyr` has lost.
+    pub fn lost(plyr: Colour) -> Self {
+        match plyr {
+            Colour::White => Self::BlackWins,
+            Colour::Black => Self::WhiteWins,
+        }
     }
 }
 
-impl<T> std::clone::Clone for Point<T>
-where
-    T: Copy,
-{
-    fn clone(&self) -> Self {
-        Self {
-            x: self.x,
-            y: self.y,
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::WhiteWins => write!(f, "White wins"),
+            Self::BlackWins => write!(f, "Black wins"),
+            Self::Draw(reason) => write!(f, "Draw by {}", reason),
         }
     }
 }
 
-struct BoardState {
-    board: [[char; 8]; 8],
-    player: Player,
-    wk_pos: (u8, u8),
-    bk_pos: (u8, u8),
-    enp_b: u8,
-    enp_w: u8,
-    castling: u8,
-    b_check: bool,
-    w_check: bool,
+/// How the fifty-move and threefold repetition rules are enforced.
+/// With `Automatic` the game is drawn as soon as either rule applies.
+/// With `Claim` the player to move may claim the draw, and the game is only
+/// drawn on its own after seventy-five moves or a fivefold repetition.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawRule {
+    Automatic,
+    Claim,
 }
 
-impl std::clone::Clone for BoardState {
-    fn clone(&self) -> Self {
-        Self {
-            board: self.board.clone(),
-            player: self.player,
-            wk_pos: self.wk_pos,
-            bk_pos: self.bk_pos,
-            enp_b: self.enp_b,
-            enp_w: self.enp_w,
-            castling: self.castling,
-            b_check: self.b_check,
-            w_check: self.w_check,
-        }
-    }
-}
+/// Returned by [`Game::play`](struct.Game.html#method.play) for a move the
+/// side to move can't make.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct IllegalMove;
 
-impl fmt::Display for BoardState {
+impl fmt::Display for IllegalMove {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut out = String::from("[\n");
-
-        for row in self.board.iter() {
-            out.push_str(&format!("\t{:?}\n", row));
-        }
-
-        out.push_str("]");
-
-        write!(f, "{}", out)
+        write!(f, "illegal move")
     }
 }
 
-pub struct RChess {
-    board: [[Color; 8]; 8],
-    board_pcs: [[char; 8]; 8],
-    current: Option<char>,
-    current_pos: Option<(u8, u8)>,
-    moves: Vec<(u8, u8)>,
-    pieces: HashMap<char, Image>,
-    turn: Player,
-    w_color: Color,
-    b_color: Color,
-    sq_size: i32,
-    moving: bool,
-    needs_draw: bool,
-    enp_b: u8,
-    enp_w: u8,
-    w_king_pos: (u8, u8),
-    b_king_pos: (u8, u8),
-    castling: u8,
-    w_check: bool,
-    b_check: bool,
+impl Error for IllegalMove {}
+
+/// A game in progress: the current position plus everything needed to
+/// apply the end-of-game rules to it, and the moves and tag pairs that go
+/// into its PGN.
+pub struct Game {
+    start: Position,
+    position: Position,
+    moves: Vec<Move>,
+    history: Vec<u64>,
+    tags: Vec<(String, String)>,
+    draw_rule: DrawRule,
+    outcome: Option<Outcome>,
 }
 
-impl RChess {
-    // Create a new instance of RChess
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        let mut pieces = HashMap::<char, Image>::new();
-
-        let board_pcs = [
-            ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r'],
-            ['p', 'p', 'p', 'p', 'p', 'p', 'p', 'p'],
-            ['-', '-', '-', '-', '-', '-', '-', '-'],
-            ['-', '-', '-', '-', '-', '-', '-', '-'],
-            ['-', '-', '-', '-', '-', '-', '-', '-'],
-            ['-', '-', '-', '-', '-', '-', '-', '-'],
-            ['P', 'P', 'P', 'P', 'P', 'P', 'P', 'P'],
-            ['R', 'N', 'B', 'Q', 'K', 'B', 'N', 'R'],
-        ];
-
-        for row in board_pcs.iter() {
-            for piece in row.iter() {
-                if pieces.contains_key(piece) {
-                    continue;
-                }
-
-                if piece != &'-' {
-                    let img = Image::new(ctx, &format!("/{}.png", piece))?;
-                    pieces.insert(*piece, img);
-                }
-            }
-        }
-
-        let w_color = Color::from_rgb(200, 200, 200);
-        let b_color = Color::from_rgb(50, 50, 50);
-
-        let mut chess = Self {
-            board: [[w_color.clone(); 8]; 8],
-            board_pcs,
-            current: None,
-            current_pos: None,
-            moves: Vec::new(),
-            pieces: pieces,
-            turn: Player::White,
-            w_color,
-            b_color,
-            sq_size: (WIN_SIZE / 8) as i32,
-            moving: false,
-            needs_draw: true,
-            enp_b: 0,
-            enp_w: 0,
-            w_king_pos: (4, 7),
-            b_king_pos: (4, 0),
-            castling: 0b1111,
-            w_check: false,
-            b_check: false,
-        };
-
-        chess.reset_board();
-
-        Ok(chess)
+impl Game {
+    /// Starts a new game from the standard starting position.
+    pub fn new(draw_rule: DrawRule) -> Self {
+        Self::from_position(Position::new(), draw_rule)
     }
 
-    // Reset the board square colors
-    fn reset_board(&mut self) {
-        for y in 0..8 {
-            let row_even = y % 2 == 0;
-
-            for x in 0..8 {
-                let col_even = x % 2 == 0;
+    /// Starts a new game from `position`, e.g. a Chess960 starting
+    /// position.
+    pub fn from_position(position: Position, draw_rule: DrawRule) -> Self {
+        let tags = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("TimeControl", "-"),
+        ];
 
-                self.board[y][x] = if (col_even && row_even) || (!col_even && !row_even) {
-                    self.w_color.clone()
-                } else {
-                    self.b_color.clone()
-                }
-            }
+        Self {
+            start: position.clone(),
+            history: vec![position.hash()],
+            position,
+            moves: Vec::new(),
+            tags: tags
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            draw_rule,
+            outcome: None,
         }
     }
 
-    /* Function that returns the current state of the board
-     */
-    fn get_board_state(&self) -> BoardState {
-        BoardState {
-            board: self.board_pcs.clone(),
-            player: self.turn,
-            wk_pos: self.w_king_pos,
-            bk_pos: self.b_king_pos,
-            castling: self.castling,
-            enp_b: self.enp_b,
-            enp_w: self.enp_w,
-            w_check: self.w_check,
-            b_check: self.b_check,
-        }
+    /// The position the game started from.
+    pub fn start(&self) -> &Position {
+        &self.start
     }
 
-    /* Checks if a piece belongs to white.
-     */
-    fn is_white_piece(pc: char) -> bool {
-        ['K', 'Q', 'R', 'N', 'B', 'P'].contains(&pc)
+    /// The current position.
+    pub fn position(&self) -> &Position {
+        &self.position
     }
 
-    /* Checks if a piece belongs to black.
-     */
-    fn is_black_piece(pc: char) -> bool {
-        ['k', 'q', 'r', 'n', 'b', 'p'].contains(&pc)
+    /// The moves played so far.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
     }
 
-    /* Checks whether the supplied piece belongs to
-     * the opponent
-     */
-    fn is_opponent(plyr: Player, ch: char) -> bool {
-        match plyr {
-            Player::White => Self::is_black_piece(ch),
-            Player::Black => Self::is_white_piece(ch),
-        }
+    /// The moves played so far in standard algebraic notation.
+    pub fn san_moves(&self) -> Vec<String> {
+        let mut position = self.start.clone();
+
+        self.moves
+            .iter()
+            .map(|mv| {
+                let san = position.san(mv);
+                position.move_piece(mv);
+                san
+            })
+            .collect()
     }
 
-    /* Checks if a given character is a piece
-     */
-    fn is_piece(ch: char) -> bool {
-        ch != '-'
+    /// The tag pairs of the game, like `Event` or `White`, in the order
+    /// they were added. `SetUp` and `FEN` are worked out from the starting
+    /// position and `Result` from the outcome when the PGN is written, so a
+    /// `Result` set here only counts while the game isn't over.
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
     }
 
-    fn move_piece_to(from: Point<u8>, to: Point<u8>, state: &mut BoardState) {
-        let x = from.x as usize;
-        let y = from.y as usize;
-
-        let ch = state.board[y][x];
-        state.enp_b = 0;
-        state.enp_w = 0;
-
-        match ch {
-            'K' => {
-                state.wk_pos = (to.x, to.y);
-                state.castling &= 0b0011;
-
-                if (from.x, from.y) == (4, 7) {
-                    if (to.x, to.y) == (6, 7) {
-                        state.board[7][5] = 'R';
-                        state.board[7][7] = '-';
-                    } else if (to.x, to.y) == (2, 7) {
-                        state.board[7][3] = 'R';
-                        state.board[7][0] = '-';
-                    }
-                }
-            }
-
-            'k' => {
-                state.bk_pos = (to.x, to.y);
-                state.castling &= 0b1100;
-
-                if (from.x, from.y) == (4, 0) {
-                    if (to.x, to.y) == (6, 0) {
-                        state.board[0][5] = 'r';
-                        state.board[0][7] = '-';
-                    } else if (to.x, to.y) == (2, 0) {
-                        state.board[0][3] = 'r';
-                        state.board[0][0] = '-';
-                    }
-                }
-            }
-
-            'p' => {
-                if from.y == 1 && to.y == 3 {
-                    state.enp_b = 0x80 >> from.x;
-                } else if from.y == 4 && from.x != to.x && state.board[5][to.x as usize] == '-' {
-                    state.board[4][to.x as usize] = '-';
-                }
-            }
-
-            'P' => {
-                if from.y == 6 && to.y == 4 {
-                    state.enp_w = 0x80 >> from.x;
-                } else if from.y == 3 && from.x != to.x && state.board[2][to.x as usize] == '-' {
-                    state.board[3][to.x as usize] = '-';
-                }
-            }
-
-            'r' => {
-                if from.x == 0 && from.y == 0 {
-                    state.castling &= 0b1101;
-                } else if from.x == 7 && from.y == 0 {
-                    state.castling &= 0b1110;
-                }
-            }
-
-            'R' => {
-                if from.x == 0 && from.y == 7 {
-                    state.castling &= 0b0111;
-                } else if from.x == 7 && from.y == 7 {
-                    state.castling &= 0b1011;
-                }
-            }
-
-            _ => (),
-        }
-
-        state.board[to.y as usize][to.x as usize] = ch;
-        state.board[y][x] = '-';
-
-        state.b_check = Self::check_for_checks(Player::Black, state);
-        state.w_check = Self::check_for_checks(Player::White, state);
+    /// The value of the tag `name`, if the game has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
     }
 
-    /* Takes a dx and dy that specifies a line of path.
-     * All squares along this path that does not have a piece
-     * are by default added to the list of moves. If a piece is encountered,
-     * a check is performed on the type. If it's an opponent piece, the piece
-     * square is added to the list of possible moves, else not
-     */
-    fn get_line_moves(pos: &Point<u8>, dpos: Point<i8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let mut m_x = pos.x as i8 + dpos.x;
-        let mut m_y = pos.y as i8 + dpos.y;
-
-        let mut moves = Vec::<(u8, u8)>::with_capacity(7);
-
-        while m_x >= 0 && m_x < 8 && m_y >= 0 && m_y < 8 {
-            let ch = state.board[m_y as usize][m_x as usize];
-
-            if Self::is_piece(ch) {
-                if Self::is_opponent(state.player, ch) {
-                    moves.push((m_x as u8, m_y as u8));
-                }
-
-                break;
-            }
-
-            moves.push((m_x as u8, m_y as u8));
-            m_x += dpos.x;
-            m_y += dpos.y;
+    /// Sets the tag `name`, replacing its old value or adding it after the
+    /// others.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
         }
-
-        moves
     }
 
-    /* Takes a position and and pushes into the move vector
-     * all the moves that a pawn at that position can make
-     */
-    fn mv_pawn(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let x_i = pos.x as usize;
-        let y_i = pos.y as usize;
-
-        let mut moves = Vec::<(u8, u8)>::with_capacity(4);
-
-        match state.player {
-            Player::White => {
-                if pos.y == 0 {
-                    return moves;
-                }
-
-                if !Self::is_piece(state.board[y_i - 1][x_i]) {
-                    moves.push((pos.x, pos.y - 1));
-                }
-
-                if pos.y == 6 && !Self::is_piece(state.board[y_i - 2][x_i]) {
-                    moves.push((pos.x, pos.y - 2));
-                }
-
-                if (pos.x < 7 && Self::is_opponent(state.player, state.board[y_i - 1][x_i + 1]))
-                    || (pos.y == 3 && pos.x < 7 && state.enp_b & (0x80 >> (pos.x + 1)) > 0)
-                {
-                    moves.push((pos.x + 1, pos.y - 1));
-                }
-
-                if (pos.x > 0 && Self::is_opponent(state.player, state.board[y_i - 1][x_i - 1]))
-                    || (pos.y == 3 && pos.x > 0 && state.enp_b & (0x80 >> (pos.x - 1)) > 0)
-                {
-                    moves.push((pos.x - 1, pos.y - 1));
-                }
-            }
-
-            Player::Black => {
-                if pos.y == 7 {
-                    return moves;
-                }
-
-                if !Self::is_piece(state.board[y_i + 1][x_i]) {
-                    moves.push((pos.x, pos.y + 1));
-                }
-
-                if pos.y == 1 && !Self::is_piece(state.board[y_i + 2][x_i]) {
-                    moves.push((pos.x, pos.y + 2));
-                }
-
-                if (pos.x < 7 && Self::is_opponent(state.player, state.board[y_i + 1][x_i + 1]))
-                    || (pos.y == 4 && pos.x < 7 && state.enp_w & (0x80 >> (pos.x + 1)) > 0)
-                {
-                    moves.push((pos.x + 1, pos.y + 1));
-                }
-
-                if (pos.x > 0 && Self::is_opponent(state.player, state.board[y_i + 1][x_i - 1]))
-                    || (pos.y == 4 && pos.x > 0 && state.enp_w & (0x80 >> (pos.x - 1)) > 0)
-                {
-                    moves.push((pos.x - 1, pos.y + 1));
-                }
-            }
-        }
-
-        moves
+    /// How the fifty-move and repetition rules are enforced in this game.
+    pub fn draw_rule(&self) -> DrawRule {
+        self.draw_rule
     }
 
-    /* Used for moving a knight. Unique function cuz
-     * knights make a 2.5 move.
-     */
-    fn mv_knight(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let x_m = pos.x as i8;
-        let y_m = pos.y as i8;
-
-        let moves: Vec<(i8, i8)> = vec![
-            (-2, -1),
-            (-1, -2),
-            (1, -2),
-            (2, -1),
-            (-2, 1),
-            (-1, 2),
-            (1, 2),
-            (2, 1),
-        ];
-
-        let mut poss_moves = Vec::<(u8, u8)>::with_capacity(8);
-
-        for (dx, dy) in moves {
-            let pos_x = x_m + dx;
-            let pos_y = y_m + dy;
-            if pos_x >= 0 && pos_x < 8 && pos_y >= 0 && pos_y < 8 {
-                let ch = state.board[pos_y as usize][pos_x as usize];
-                if !Self::is_piece(ch) || Self::is_opponent(state.player, ch) {
-                    poss_moves.push((pos_x as u8, pos_y as u8));
-                }
-            }
-        }
-
-        poss_moves
+    /// The result of the game, once it's over.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
     }
 
-    /* Used for moving a bishop
-     */
-    fn mv_bishop(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let mut moves = Vec::<(u8, u8)>::with_capacity(13);
-        moves.append(&mut Self::get_line_moves(&pos, Point::new(1, 1), state));
-        moves.append(&mut Self::get_line_moves(&pos, Point::new(1, -1), state));
-        moves.append(&mut Self::get_line_moves(&pos, Point::new(-1, -1), state));
-        moves.append(&mut Self::get_line_moves(&pos, Point::new(-1, 1), state));
-        moves
-    }
-
-    /* Used for moving a Rook
-     */
-    fn mv_rook(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let mut moves = Vec::<(u8, u8)>::with_capacity(14);
-        for (dx, dy) in &[(0, 1), (0, -1), (1, 0), (-1, 0)] {
-            moves.append(&mut Self::get_line_moves(&pos, Point::new(*dx, *dy), state));
+    /// Plays a move for the side to move. Fails if the game is over or the
+    /// move isn't legal.
+    pub fn play(&mut self, mv: &Move) -> Result<(), IllegalMove> {
+        if self.outcome.is_some() || !self.position.legal_moves().contains(mv) {
+            return Err(IllegalMove);
         }
 
-        moves
-    }
+        self.position.move_piece(mv);
+        self.moves.push(*mv);
+        self.history.push(self.position.hash());
 
-    /* Used for moving a Queen
-     */
-    fn mv_queen(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let mut moves = Vec::<(u8, u8)>::with_capacity(28);
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-
-                moves.append(&mut Self::get_line_moves(&pos, Point::new(dx, dy), state));
-            }
-        }
+        self.outcome = self
+            .position
+            .check_for_game_over()
+            .or_else(|| self.check_for_draw());
 
-        moves
+        Ok(())
     }
 
-    /* Used for moving a King
-     */
-    fn mv_king(pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        let mut moves = Vec::<(u8, u8)>::with_capacity(8);
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if pos.x == 0 && pos.y == 0 {
-                    continue;
-                }
-
-                let x_m = pos.x as i8 + dx;
-                let y_m = pos.y as i8 + dy;
-
-                if x_m >= 0 && x_m < 8 && y_m >= 0 && y_m < 8 {
-                    let ch = state.board[y_m as usize][x_m as usize];
-                    if !Self::is_piece(ch) || Self::is_opponent(state.player, ch) {
-                        moves.push((x_m as u8, y_m as u8));
-                    }
-                }
-            }
-        }
-
-        let ch = state.board[pos.y as usize][pos.x as usize];
-
-        let (checked, q_side, k_side, plyr) = match ch {
-            'k' => (
-                state.b_check,
-                state.castling & 0b0010 > 0,
-                state.castling & 0b0001 > 0,
-                Player::Black,
-            ),
-            'K' => (
-                state.w_check,
-                state.castling & 0b1000 > 0,
-                state.castling & 0b0100 > 0,
-                Player::White,
-            ),
-            _ => (false, false, false, Player::White),
+    /// Counts how many times the current position has occurred.
+    pub fn repetitions(&self) -> usize {
+        // Only the positions since the last capture or pawn move need to be looked at
+        let current = match self.history.last() {
+            Some(key) => key,
+            None => return 0,
         };
 
-        if checked {
-            return moves;
-        }
-
-        let y = pos.y as usize;
-
-        if k_side {
-            let mut accept = true;
-            for x in 5..=6 {
-                if Self::is_piece(state.board[y][x as usize]) {
-                    accept = false;
-                    break;
-                }
-                let mut state_ = state.clone();
-                Self::move_piece_to(pos.clone(), Point::new(x, pos.y), &mut state_);
-                let checked = match plyr {
-                    Player::White => state_.w_check,
-                    Player::Black => state_.b_check,
-                };
-
-                if checked {
-                    accept = false;
-                    break;
-                }
-            }
-
-            if accept {
-                moves.push((6, pos.y));
-            }
-        }
-
-        if q_side {
-            let mut accept = true;
-            for x in 2..=3 {
-                if Self::is_piece(state.board[y][x as usize]) {
-                    accept = false;
-                    break;
-                }
-                let mut state_ = state.clone();
-                Self::move_piece_to(pos.clone(), Point::new(x, pos.y), &mut state_);
-                let checked = match plyr {
-                    Player::White => state_.w_check,
-                    Player::Black => state_.b_check,
-                };
-
-                if checked {
-                    accept = false;
-                    break;
-                }
-            }
-
-            if accept {
-                moves.push((2, pos.y));
-            }
-        }
-
-        moves
-    }
-
-    /* Takes a piece and a position and returns all possible moves for the piece.
-     */
-    fn get_piece_moves(ch: char, pos: Point<u8>, state: &BoardState) -> Vec<(u8, u8)> {
-        match ch {
-            'p' | 'P' => Self::mv_pawn(pos, state),
-            'r' | 'R' => Self::mv_rook(pos, state),
-            'n' | 'N' => Self::mv_knight(pos, state),
-            'b' | 'B' => Self::mv_bishop(pos, state),
-            'q' | 'Q' => Self::mv_queen(pos, state),
-            'k' | 'K' => Self::mv_king(pos, state),
-            _ => Vec::<(u8, u8)>::new(),
-        }
-    }
-
-    /* This function is called when the player clicks the board and when
-     * a move is currently not in progress. The position of the clicked
-     * square is passed to the function. If the player has clicked on one
-     * of their own pieces, the piece's moves are added to self.moves,
-     * the colors of the move squares are changed, the color of the square
-     * under the piece is changed, the game state is set to 'moving' and the
-     * info about the current piece is stored.
-     */
-    fn select_piece(&mut self, x: u8, y: u8) {
-        let ch = self.board_pcs[y as usize][x as usize];
-
-        if !Self::is_piece(ch) || Self::is_opponent(self.turn, ch) {
-            return;
-        }
-
-        let board_state = self.get_board_state();
-
-        let moves = Self::get_piece_moves(ch, Point::new(x, y), &board_state);
-
-        self.current = Some(ch);
-        self.current_pos = Some((x, y));
-
-        for (m_x, m_y) in &moves {
-            let mut state = board_state.clone();
-            Self::move_piece_to(Point::new(x, y), Point::new(*m_x, *m_y), &mut state);
-            let checked = match self.turn {
-                Player::White => state.w_check,
-                Player::Black => state.b_check,
-            };
-
-            if checked {
-                continue;
-            }
-
-            self.board[*m_y as usize][*m_x as usize] = Color::from_rgb(200, 200, 0);
-
-            self.moves.push((*m_x, *m_y));
-        }
-
-        self.board[y as usize][x as usize] = Color::from_rgb(255, 85, 85);
-
-        self.needs_draw = true;
-        self.moving = true;
+        self.history
+            .iter()
+            .rev()
+            .take(self.position.halfmove() as usize + 1)
+            .filter(|key| *key == current)
+            .count()
     }
 
-    fn move_piece(&mut self, x: u8, y: u8) -> bool {
-        if self.moves.contains(&(x, y)) {
-            let mut state = self.get_board_state();
-            let curr = self.current_pos.unwrap();
-            Self::move_piece_to(Point::new(curr.0, curr.1), Point::new(x, y), &mut state);
-            self.set_state(&state);
-            self.current = None;
-            self.current_pos = None;
-            self.moving = false;
-            self.turn = self.turn.switch();
-            state.player = state.player.switch();
-            self.moves.clear();
-            self.needs_draw = true;
-            self.reset_board();
-
-            if Self::check_for_checkmate(self.turn, &state) {
-                return true;
-            } else {
-                return false;
-            }
-        }
-
-        let ch = self.board_pcs[y as usize][x as usize];
-
-        if Self::is_piece(ch) && !Self::is_opponent(self.turn, ch) {
-            self.moves.clear();
-            self.reset_board();
-            self.select_piece(x, y);
-            self.needs_draw = true;
+    /// Called when the player to move claims a draw under `DrawRule::Claim`.
+    /// The claim only succeeds, ending the game, if the fifty-move rule or
+    /// threefold repetition applies.
+    pub fn claim_draw(&mut self) -> bool {
+        if self.outcome.is_some() || self.draw_rule != DrawRule::Claim {
+            return false;
         }
 
-        false
-    }
+        let reason = if self.position.halfmove() >= 100 {
+            DrawReason::FiftyMoves
+        } else if self.repetitions() >= 3 {
+            DrawReason::ThreefoldRepetition
+        } else {
+            return false;
+        };
 
-    fn set_state(&mut self, state: &BoardState) {
-        self.board_pcs = state.board;
-        self.w_king_pos = state.wk_pos;
-        self.b_king_pos = state.bk_pos;
-        self.enp_b = state.enp_b;
-        self.enp_w = state.enp_w;
-        self.castling = state.castling;
-        self.w_check = state.w_check;
-        self.b_check = state.b_check;
+        self.outcome = Some(Outcome::Draw(reason));
+        true
     }
 
-    fn check_for_checks(plyr: Player, state: &mut BoardState) -> bool {
-        let orig = state.player;
-        state.player = plyr.switch();
-        for y in 0..8 {
-            for x in 0..8 {
-                let ch = state.board[y as usize][x as usize];
-
-                let is_valid_piece = match plyr {
-                    Player::White => Self::is_black_piece(ch),
-                    Player::Black => Self::is_white_piece(ch),
-                };
-
-                if !is_valid_piece {
-                    continue;
-                }
-
-                let k_pos = match plyr {
-                    Player::White => &state.wk_pos,
-                    Player::Black => &state.bk_pos,
-                };
-
-                if Self::get_piece_moves(ch, Point::new(x, y), state).contains(k_pos) {
-                    state.player = orig;
-                    return true;
-                }
-            }
+    /// Ends the game because `flagged` ran out of time. The game is only
+    /// lost if the opponent could still have checkmated.
+    pub fn timeout(&mut self, flagged: Colour) {
+        if self.outcome.is_some() {
+            return;
         }
 
-        state.player = orig;
-        false
+        self.outcome = if self.position.has_mating_material(flagged.switch()) {
+            Some(Outcome::lost(flagged))
+        } else {
+            Some(Outcome::Draw(DrawReason::TimeoutVsInsufficientMaterial))
+        };
     }
 
-    fn check_for_checkmate(plyr: Player, state: &BoardState) -> bool {
-        for y in 0..8 {
-            for x in 0..8 {
-                let ch = state.board[y as usize][x as usize];
-
-                let is_valid_piece = match plyr {
-                    Player::White => Self::is_white_piece(ch),
-                    Player::Black => Self::is_black_piece(ch),
-                };
-
-                if !is_valid_piece {
-                    continue;
-                }
-
-                for (m_x, m_y) in Self::get_piece_moves(ch, Point::new(x, y), state) {
-                    let mut state_ = state.clone();
-                    Self::move_piece_to(Point::new(x, y), Point::new(m_x, m_y), &mut state_);
-
-                    let checked = match plyr {
-                        Player::White => state_.w_check,
-                        Player::Black => state_.b_check,
-                    };
-
-                    if !checked {
-                        return false;
-                    }
-                }
-            }
-        }
-        true
+    /* Replaces all the tag pairs, for a game being read back.
+     */
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_tags(&mut self, tags: Vec<(String, String)>) {
+        self.tags = tags;
     }
-}
 
-impl EventHandler for RChess {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-        Ok(())
+    /* Sets how a game being read back ended, for outcomes like claimed
+     * draws that replaying the moves doesn't bring about.
+     */
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_outcome(&mut self, outcome: Option<Outcome>) {
+        self.outcome = outcome;
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        if !self.needs_draw {
-            return Ok(());
+    /* Checks for dead positions and the fifty-move and repetition rules.
+     * Under `DrawRule::Claim` only the seventy-five-move and fivefold
+     * repetition rules end the game without a claim.
+     */
+    fn check_for_draw(&self) -> Option<Outcome> {
+        if self.position.is_dead() {
+            return Some(Outcome::Draw(DrawReason::InsufficientMaterial));
         }
-        graphics::clear(ctx, Color::from_rgb(0, 0, 0));
-
-        for (y, row) in self.board.iter().enumerate() {
-            for (x, cell) in row.iter().enumerate() {
-                let x_sq = x as i32 * self.sq_size;
-                let y_sq = y as i32 * self.sq_size;
-
-                let r = graphics::Rect::new_i32(x_sq, y_sq, self.sq_size, self.sq_size);
-                let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, *cell)?;
-
-                graphics::draw(ctx, &mesh, DrawParam::default())?;
-
-                let ch = self.board_pcs[y][x];
 
-                if Self::is_piece(ch) {
-                    let img = match self.pieces.get(&ch) {
-                        Some(i) => i,
-                        None => continue,
-                    };
-
-                    let ddraw = (self.sq_size as f32 - img.width() as f32 * 1.5) / 2.;
-                    let x_draw = x_sq as f32 + ddraw;
-                    let y_draw = y_sq as f32 + ddraw;
-                    let draw_param = DrawParam::new().dest([x_draw, y_draw]).scale([1.5, 1.5]);
+        let (moves, reps) = match self.draw_rule {
+            DrawRule::Automatic => (100, 3),
+            DrawRule::Claim => (150, 5),
+        };
 
-                    graphics::draw(ctx, img, draw_param)?;
-                }
+        let reason = if self.position.halfmove() >= moves {
+            match self.draw_rule {
+                DrawRule::Automatic => DrawReason::FiftyMoves,
+                DrawRule::Claim => DrawReason::SeventyFiveMoves,
             }
-        }
-
-        self.needs_draw = false;
-
-        graphics::present(ctx)
-    }
-
-    fn mouse_button_down_event(&mut self, ctx: &mut Context, btn: MouseButton, x: f32, y: f32) {
-        let x = (x as i32 / self.sq_size) as u8;
-        let y = (y as i32 / self.sq_size) as u8;
-
-        match btn {
-            MouseButton::Left => {
-                if !self.moving {
-                    self.select_piece(x, y);
-                } else {
-                    let mated = self.move_piece(x, y);
-
-                    if mated {
-                        ggez::event::quit(ctx);
-                    }
-                }
+        } else if self.repetitions() >= reps {
+            match self.draw_rule {
+                DrawRule::Automatic => DrawReason::ThreefoldRepetition,
+                DrawRule::Claim => DrawReason::FivefoldRepetition,
             }
+        } else {
+            return None;
+        };
 
-            _ => (),
-        }
+        Some(Outcome::Draw(reason))
     }
 }
@@ -0,0 +1,951 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use clipboard::{ClipboardContext, ClipboardProvider};
+use ggez::{
+    event::{EventHandler, KeyCode, KeyMods, MouseButton},
+    graphics::{self, Color, DrawMode, DrawParam, Image, Scale, Text, TextFragment},
+    Context, GameResult,
+};
+
+use std::vec::Vec;
+
+use chess_rs::{
+    pgn, Colour, DrawRule, Engine, Game, Limit, Move, Outcome, ParseSanError, PgnGame, Piece,
+    PieceKind, Position, SearchResult, Square,
+};
+
+use crate::WIN_SIZE;
+
+// How long the computer thinks about each move
+const THINK_TIME: Duration = Duration::from_secs(1);
+
+/* Games read from a PGN file, and how far into which of them the board
+ * is.
+ */
+struct Replay {
+    games: Vec<PgnGame>,
+    index: usize,
+    ply: usize,
+    draw_rule: DrawRule,
+}
+
+pub struct RChess {
+    board: [[Color; 8]; 8],
+    game: Game,
+    moves: Vec<Move>,
+    pieces: HashMap<Piece, Image>,
+    w_color: Color,
+    b_color: Color,
+    sq_size: i32,
+    moving: bool,
+    promoting: Option<Square>,
+    chess960: bool,
+    pgn_path: Option<PathBuf>,
+    saved: bool,
+    replay: Option<Replay>,
+    entry: Option<String>,
+    entry_error: Option<ParseSanError>,
+    engine: Arc<Engine>,
+    computer: Option<Colour>,
+    thinking: Option<(u64, Receiver<SearchResult>)>,
+    tablebase: Option<(u64, Option<i32>)>,
+    probing: Option<(u64, Receiver<Option<i32>>)>,
+    show_book: bool,
+    needs_draw: bool,
+}
+
+impl RChess {
+    // Create a new instance of RChess, starting from `position`. Finished
+    // games are added to the PGN file at `pgn_path`, if there is one
+    pub fn new(
+        ctx: &mut Context,
+        draw_rule: DrawRule,
+        position: Position,
+        pgn_path: Option<PathBuf>,
+    ) -> GameResult<Self> {
+        let mut pieces = HashMap::<Piece, Image>::new();
+
+        for colour in Colour::ALL.iter() {
+            for kind in PieceKind::ALL.iter() {
+                let piece = Piece::new(*colour, *kind);
+                let img = Image::new(ctx, format!("/{}.png", piece))?;
+                pieces.insert(piece, img);
+            }
+        }
+
+        let w_color = Color::from_rgb(200, 200, 200);
+        let b_color = Color::from_rgb(50, 50, 50);
+
+        let mut chess = Self {
+            board: [[w_color; 8]; 8],
+            game: Game::new(draw_rule),
+            moves: Vec::new(),
+            pieces,
+            w_color,
+            b_color,
+            sq_size: (WIN_SIZE / 8) as i32,
+            moving: false,
+            promoting: None,
+            chess960: false,
+            pgn_path,
+            saved: false,
+            replay: None,
+            entry: None,
+            entry_error: None,
+            engine: Arc::new(Engine::new()),
+            computer: None,
+            thinking: None,
+            tablebase: None,
+            probing: None,
+            show_book: false,
+            needs_draw: true,
+        };
+
+        chess.start_from(ctx, position);
+
+        Ok(chess)
+    }
+
+    /// Uses `engine` for the computer's moves and the book moves shown
+    /// with B. The computer plays `computer`, if that's one of the sides.
+    pub fn set_engine(&mut self, engine: Engine, computer: Option<Colour>) {
+        self.engine = Arc::new(engine);
+        self.computer = computer;
+    }
+
+    /* Starts over, keeping the draw rule of the previous game. In Chess960
+     * mode every game gets a new random starting position.
+     */
+    fn new_game(&mut self) {
+        let position = if self.chess960 {
+            Position::random_chess960()
+        } else {
+            Position::new()
+        };
+
+        self.reset_game(position);
+    }
+
+    /* Starts a game from `position`, switching to Chess960 mode if it's a
+     * Chess960 position and back if it isn't.
+     */
+    fn start_from(&mut self, ctx: &Context, position: Position) {
+        self.chess960 = position.is_chess960();
+        self.reset_game(position);
+        self.set_title(ctx);
+    }
+
+    /* Replaces the game with a new one from `position` and clears the
+     * selection.
+     */
+    fn reset_game(&mut self, position: Position) {
+        self.game = Game::from_position(position, self.game.draw_rule());
+        self.game.set_tag("Date", &pgn::date(SystemTime::now()));
+        self.saved = false;
+        self.replay = None;
+        self.entry = None;
+        self.moves.clear();
+        self.moving = false;
+        self.promoting = None;
+        self.needs_draw = true;
+        self.reset_board();
+    }
+
+    // Reset the board square colors
+    fn reset_board(&mut self) {
+        for y in 0..8 {
+            let row_even = y % 2 == 0;
+
+            for x in 0..8 {
+                let col_even = x % 2 == 0;
+
+                self.board[y][x] = if (col_even && row_even) || (!col_even && !row_even) {
+                    self.w_color
+                } else {
+                    self.b_color
+                }
+            }
+        }
+    }
+
+    /* Switches between standard chess and Chess960 and starts a new game.
+     * A game that has started has to be over first, so it isn't thrown
+     * away by a stray key.
+     */
+    fn toggle_chess960(&mut self, ctx: &Context) -> Result<(), Box<dyn Error>> {
+        let started = !self.game.moves().is_empty() && self.game.outcome().is_none();
+
+        if started && self.replay.is_none() {
+            return Err("finish the game before switching between chess and Chess960".into());
+        }
+
+        self.chess960 = !self.chess960;
+        self.new_game();
+        self.set_title(ctx);
+
+        Ok(())
+    }
+
+    /* Shows in the window title whether Chess960 is on, or which game is
+     * being replayed.
+     */
+    fn set_title(&self, ctx: &Context) {
+        let title = match &self.replay {
+            Some(replay) => {
+                let game = &replay.games[replay.index];
+
+                format!(
+                    "Chess.rs - {} vs {} ({}/{})",
+                    game.tag("White").unwrap_or("?"),
+                    game.tag("Black").unwrap_or("?"),
+                    replay.index + 1,
+                    replay.games.len()
+                )
+            }
+            None if self.chess960 => String::from("Chess.rs - Chess960"),
+            None => String::from("Chess.rs"),
+        };
+
+        graphics::set_window_title(ctx, &title);
+    }
+
+    /// Shows the first of `games` from its starting position. The arrow keys
+    /// then step through the main line and switch between the games.
+    pub fn replay(&mut self, ctx: &Context, games: Vec<PgnGame>) {
+        if games.is_empty() {
+            return;
+        }
+
+        let draw_rule = self.game.draw_rule();
+        self.reset_game(Position::new());
+
+        self.replay = Some(Replay {
+            games,
+            index: 0,
+            ply: 0,
+            draw_rule,
+        });
+
+        self.show_replay(ctx, 0, 0);
+    }
+
+    /* Moves the replay to `ply` moves into the game at `index`, staying
+     * within the games and their main lines.
+     */
+    fn show_replay(&mut self, ctx: &Context, index: usize, ply: usize) {
+        let replay = match &mut self.replay {
+            Some(replay) => replay,
+            None => return,
+        };
+
+        replay.index = index.min(replay.games.len() - 1);
+
+        let game = &replay.games[replay.index];
+        replay.ply = ply.min(game.main_line.moves.len());
+
+        // Archived games often go on past a repetition nobody claimed
+        self.game = game.to_game(replay.ply, DrawRule::Claim);
+        self.needs_draw = true;
+        self.set_title(ctx);
+    }
+
+    /* Handles the keys of the replay mode: left and right step through the
+     * moves, Home and End jump to the start and the end, up and down switch
+     * games and Enter leaves the replay to play on from the position shown.
+     */
+    fn replay_key(&mut self, ctx: &Context, key: KeyCode) {
+        let (index, ply, draw_rule) = match &self.replay {
+            Some(replay) => (replay.index, replay.ply, replay.draw_rule),
+            None => return,
+        };
+
+        match key {
+            KeyCode::Left => self.show_replay(ctx, index, ply.saturating_sub(1)),
+            KeyCode::Right => self.show_replay(ctx, index, ply + 1),
+            KeyCode::Home => self.show_replay(ctx, index, 0),
+            KeyCode::End => self.show_replay(ctx, index, usize::MAX),
+            KeyCode::Up => self.show_replay(ctx, index.saturating_sub(1), 0),
+            KeyCode::Down => self.show_replay(ctx, index + 1, 0),
+            KeyCode::Return => {
+                if let Some(replay) = self.replay.take() {
+                    let game = replay.games[replay.index].to_game(ply, draw_rule);
+
+                    self.chess960 = game.start().is_chess960();
+                    self.game = game;
+                    self.saved = false;
+                    self.needs_draw = true;
+                    self.set_title(ctx);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /* Puts the FEN of the current position on the clipboard.
+     */
+    fn copy_fen(&self) -> Result<(), Box<dyn Error>> {
+        let mut clipboard: ClipboardContext = ClipboardProvider::new()?;
+
+        clipboard.set_contents(self.game.position().to_fen())
+    }
+
+    /* Starts a new game from the FEN on the clipboard.
+     */
+    fn paste_fen(&mut self, ctx: &Context) -> Result<(), Box<dyn Error>> {
+        let mut clipboard: ClipboardContext = ClipboardProvider::new()?;
+        let position = Position::from_fen(clipboard.get_contents()?.trim())?;
+
+        self.start_from(ctx, position);
+
+        Ok(())
+    }
+
+    /* Adds the game as PGN to the end of the PGN file, `games.pgn` unless
+     * another one was given with --pgn.
+     */
+    fn save_pgn(&mut self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.pgn_path {
+            Some(path) => path.clone(),
+            None => PathBuf::from("games.pgn"),
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", self.game.to_pgn())?;
+
+        self.saved = true;
+
+        Ok(())
+    }
+
+    /* Saves the game once it's over, if finished games are being kept.
+     */
+    fn save_finished_game(&mut self) {
+        if self.game.outcome().is_none() || self.saved || self.pgn_path.is_none() {
+            return;
+        }
+
+        if let Err(e) = self.save_pgn() {
+            eprintln!("{}", e);
+        }
+    }
+
+    /* The square under the window coordinates `(x, y)`, with the 8th rank
+     * at the top.
+     */
+    fn square_at(x: u8, y: u8) -> Square {
+        Square::new(x, 7 - y)
+    }
+
+    /* The window coordinates of `sq`, the other way round from `square_at`.
+     */
+    fn coords(sq: Square) -> (usize, usize) {
+        (sq.file() as usize, 7 - sq.rank() as usize)
+    }
+
+    /* The square to click for a move. Castling in Chess960 is done by
+     * clicking the rook, as the king might not move at all or only move
+     * one square, just like it could without castling.
+     */
+    fn move_target(&self, mv: &Move) -> Square {
+        let position = self.game.position();
+
+        match position.castling_rook(mv) {
+            Some(rook) if position.is_chess960() => rook,
+            _ => mv.to,
+        }
+    }
+
+    /* This function is called when the player clicks the board and when
+     * a move is currently not in progress. The position of the clicked
+     * square is passed to the function. If the player has clicked on one
+     * of their own pieces, the piece's moves are added to self.moves,
+     * the colors of the move squares are changed, the color of the square
+     * under the piece is changed and the game state is set to 'moving'.
+     */
+    fn select_piece(&mut self, x: u8, y: u8) {
+        let position = self.game.position();
+        let sq = Self::square_at(x, y);
+
+        match position.piece_at(sq) {
+            Some(pc) if pc.colour == position.player() => (),
+            _ => return,
+        }
+
+        self.moves = position.piece_moves(sq);
+
+        for mv in &self.moves {
+            let (x, y) = Self::coords(self.move_target(mv));
+            self.board[y][x] = Color::from_rgb(200, 200, 0);
+        }
+
+        self.board[y as usize][x as usize] = Color::from_rgb(255, 85, 85);
+
+        self.needs_draw = true;
+        self.moving = true;
+    }
+
+    /* Called with the clicked square while a piece is selected. A click on
+     * one of the highlighted squares makes the move, unless it's a pawn
+     * reaching the last rank, in which case the promotion chooser is opened
+     * instead and the move is finished by `choose_promotion`.
+     */
+    fn move_piece(&mut self, x: u8, y: u8) {
+        let sq = Self::square_at(x, y);
+        let clicked = self.moves.iter().find(|mv| self.move_target(mv) == sq);
+
+        if let Some(mv) = clicked.copied() {
+            if mv.promotion.is_some() {
+                self.promoting = Some(sq);
+                self.needs_draw = true;
+            } else {
+                self.commit_move(&mv);
+            }
+
+            return;
+        }
+
+        let position = self.game.position();
+
+        if position.piece_at(sq).map(|pc| pc.colour) == Some(position.player()) {
+            self.moves.clear();
+            self.reset_board();
+            self.select_piece(x, y);
+            self.needs_draw = true;
+        }
+    }
+
+    /* Plays one of the selected piece's moves and hands the turn over.
+     */
+    fn commit_move(&mut self, mv: &Move) {
+        // Only legal moves get here
+        let _ = self.game.play(mv);
+        self.save_finished_game();
+
+        self.promoting = None;
+        self.moving = false;
+        self.moves.clear();
+        self.needs_draw = true;
+        self.reset_board();
+    }
+
+    /* Returns the squares covered by the promotion chooser along with the
+     * piece each of them stands for. The chooser hangs from the promotion
+     * square towards the middle of the board, queen first.
+     */
+    fn promotion_squares(&self) -> Vec<(Square, PieceKind)> {
+        let sq = match self.promoting {
+            Some(sq) => sq,
+            None => return Vec::new(),
+        };
+
+        let dr = match self.game.position().player() {
+            Colour::White => -1,
+            Colour::Black => 1,
+        };
+
+        PieceKind::PROMOTIONS
+            .iter()
+            .enumerate()
+            .filter_map(|(i, kind)| sq.offset(0, dr * i as i8).map(|sq| (sq, *kind)))
+            .collect()
+    }
+
+    /* Handles a click while the promotion chooser is open. Clicking one of
+     * the offered pieces finishes the move, clicking anywhere else cancels
+     * the promotion and keeps the pawn selected.
+     */
+    fn choose_promotion(&mut self, x: u8, y: u8) {
+        let choice = self
+            .promotion_squares()
+            .into_iter()
+            .find(|(sq, _)| *sq == Self::square_at(x, y))
+            .and_then(|(_, kind)| {
+                self.moves
+                    .iter()
+                    .find(|mv| Some(mv.to) == self.promoting && mv.promotion == Some(kind))
+                    .copied()
+            });
+
+        match choice {
+            Some(mv) => self.commit_move(&mv),
+            None => {
+                self.promoting = None;
+                self.needs_draw = true;
+            }
+        }
+    }
+
+    /* Handles the keys that edit a move being typed in: Enter plays it,
+     * Backspace takes back a character and Escape gives up on it.
+     */
+    fn entry_key(&mut self, key: KeyCode) {
+        let humans_turn = self.humans_turn();
+        let text = match &mut self.entry {
+            Some(text) => text,
+            None => return,
+        };
+
+        match key {
+            KeyCode::Escape => self.entry = None,
+            KeyCode::Back => {
+                text.pop();
+                self.entry_error = None;
+            }
+            KeyCode::Return if !humans_turn => return,
+            KeyCode::Return => match self.game.position().parse_san(text) {
+                Ok(mv) => {
+                    self.entry = None;
+                    self.commit_move(&mv);
+                }
+                Err(e) => self.entry_error = Some(e),
+            },
+            _ => return,
+        }
+
+        self.needs_draw = true;
+    }
+
+    /* Shows the move being typed in along the bottom of the board, with
+     * the reason it couldn't be played if it was refused.
+     */
+    fn draw_entry(&self, ctx: &mut Context, text: &str) -> GameResult<()> {
+        let size = self.sq_size * 8;
+        let r = graphics::Rect::new_i32(0, size - 60, size, 60);
+        let shade = Color::from_rgba(0, 0, 0, 200);
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, shade)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let line = match self.entry_error {
+            Some(e) => format!("Move: {} ({})", text, e),
+            None => format!("Move: {}_", text),
+        };
+
+        let colour = match self.entry_error {
+            Some(_) => Color::from_rgb(255, 85, 85),
+            None => Color::from_rgb(240, 240, 240),
+        };
+
+        let fragment = TextFragment::new(line.as_str())
+            .scale(Scale::uniform(32.))
+            .color(colour);
+
+        graphics::draw(
+            ctx,
+            &Text::new(fragment),
+            DrawParam::new().dest([16., size as f32 - 46.]),
+        )
+    }
+
+    /* Whether the side to move is played from this window rather than by
+     * the computer.
+     */
+    fn humans_turn(&self) -> bool {
+        self.computer != Some(self.game.position().player())
+    }
+
+    /* Plays the computer's move once it has found one, and sets it
+     * thinking in the background when its turn comes. A move found for a
+     * position that's no longer on the board is thrown away.
+     */
+    fn run_computer(&mut self) {
+        if let Some((key, thinking)) = &self.thinking {
+            let result = match thinking.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.thinking = None;
+                    return;
+                }
+            };
+
+            let current = *key == self.game.position().hash();
+            self.thinking = None;
+
+            if let (true, Some(mv)) = (current, result.best_move) {
+                self.commit_move(&mv);
+            }
+
+            return;
+        }
+
+        if self.humans_turn() || self.game.outcome().is_some() || self.replay.is_some() {
+            return;
+        }
+
+        let position = self.game.position().clone();
+        let engine = Arc::clone(&self.engine);
+        let (sender, receiver) = mpsc::channel();
+
+        self.thinking = Some((position.hash(), receiver));
+
+        thread::spawn(move || {
+            let _ = sender.send(engine.think(&position, Limit::Time(THINK_TIME)));
+        });
+    }
+
+    /* Looks the current position up in the tablebases in the background
+     * whenever it changes, the way the computer thinks, and keeps the
+     * distance to zero found for drawing.
+     */
+    fn run_tablebase(&mut self) {
+        if let Some((key, probing)) = &self.probing {
+            let dtz = match probing.try_recv() {
+                Ok(dtz) => dtz,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => None,
+            };
+
+            self.tablebase = Some((*key, dtz));
+            self.probing = None;
+            self.needs_draw = true;
+        }
+
+        let position = self.game.position().clone();
+        let key = position.hash();
+
+        if matches!(self.tablebase, Some((probed, _)) if probed == key) {
+            return;
+        }
+
+        let max_pieces = match self.engine.tablebases() {
+            Some(tablebases) => tablebases.max_pieces(),
+            None => return,
+        };
+
+        // Most positions have too many pieces to be worth a thread
+        if position.occupied().count_ones() as usize > max_pieces {
+            self.tablebase = Some((key, None));
+            return;
+        }
+
+        let engine = Arc::clone(&self.engine);
+        let (sender, receiver) = mpsc::channel();
+
+        self.probing = Some((key, receiver));
+
+        thread::spawn(move || {
+            let dtz = engine
+                .tablebases()
+                .and_then(|tablebases| tablebases.probe_dtz(&position));
+            let _ = sender.send(dtz);
+        });
+    }
+
+    /* Shows or hides the book moves of the current position.
+     */
+    fn toggle_book(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.engine.book().is_none() {
+            return Err("no opening book, start with --book <file>".into());
+        }
+
+        self.show_book = !self.show_book;
+        self.needs_draw = true;
+
+        Ok(())
+    }
+
+    /* Marks the squares the book moves go from and to, and lists the moves
+     * with how often the book plays them along the top of the board.
+     */
+    fn draw_book_moves(&self, ctx: &mut Context) -> GameResult<()> {
+        let book = match self.engine.book() {
+            Some(book) => book,
+            None => return Ok(()),
+        };
+
+        let position = self.game.position();
+        let moves = book.moves(position);
+        let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+        let tint = Color::from_rgba(60, 120, 255, 90);
+
+        for (mv, _) in &moves {
+            for sq in [mv.from, self.move_target(mv)].iter() {
+                let (x, y) = Self::coords(*sq);
+                let r = graphics::Rect::new_i32(
+                    x as i32 * self.sq_size,
+                    y as i32 * self.sq_size,
+                    self.sq_size,
+                    self.sq_size,
+                );
+                let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, tint)?;
+
+                graphics::draw(ctx, &mesh, DrawParam::default())?;
+            }
+        }
+
+        let listed: Vec<String> = moves
+            .iter()
+            .map(|(mv, weight)| match total {
+                0 => position.san(mv),
+                _ => format!("{} {}%", position.san(mv), *weight as u32 * 100 / total),
+            })
+            .collect();
+
+        let line = if listed.is_empty() {
+            String::from("Book: out of book")
+        } else {
+            format!("Book: {}", listed.join(", "))
+        };
+
+        let size = self.sq_size * 8;
+        let r = graphics::Rect::new_i32(0, 0, size, 40);
+        let shade = Color::from_rgba(0, 0, 0, 200);
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, shade)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let text = Text::new(TextFragment::new(line.as_str()).scale(Scale::uniform(24.)));
+        graphics::draw(ctx, &text, DrawParam::new().dest([12., 8.]))
+    }
+
+    /* Shows what the tablebases said about the current position along the
+     * bottom of the board, once the probe is back and if they cover it.
+     */
+    fn draw_tablebase(&self, ctx: &mut Context) -> GameResult<()> {
+        let position = self.game.position();
+        let dtz = match self.tablebase {
+            Some((key, Some(dtz))) if key == position.hash() => dtz,
+            _ => return Ok(()),
+        };
+
+        let line = match dtz {
+            0 => String::from("Tablebase: draw"),
+            dtz if dtz.abs() > 100 => String::from("Tablebase: draw under the fifty-move rule"),
+            dtz => {
+                let winner = if dtz > 0 {
+                    position.player()
+                } else {
+                    position.player().switch()
+                };
+
+                format!("Tablebase: win in {} for {}", dtz.abs(), winner)
+            }
+        };
+
+        let size = self.sq_size * 8;
+        let r = graphics::Rect::new_i32(0, size - 40, size, 40);
+        let shade = Color::from_rgba(0, 0, 0, 200);
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, shade)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let text = Text::new(TextFragment::new(line.as_str()).scale(Scale::uniform(24.)));
+        graphics::draw(ctx, &text, DrawParam::new().dest([12., size as f32 - 32.]))
+    }
+
+    /* Called when the player to move claims a draw.
+     */
+    fn claim_draw(&mut self) {
+        if self.game.claim_draw() {
+            self.save_finished_game();
+            self.promoting = None;
+            self.needs_draw = true;
+        }
+    }
+
+    /* Dims the board and shows the result of the game on top of it.
+     */
+    fn draw_game_over(&self, ctx: &mut Context, outcome: Outcome) -> GameResult<()> {
+        let size = self.sq_size * 8;
+        let r = graphics::Rect::new_i32(0, 0, size, size);
+        let shade = Color::from_rgba(0, 0, 0, 170);
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, shade)?;
+
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let lines = [
+            (format!("{}", outcome), 48.),
+            (String::from("Click anywhere to start a new game"), 24.),
+        ];
+
+        let mut y_draw = size as f32 / 2. - 40.;
+
+        for (line, scale) in lines.iter() {
+            let text = Text::new(TextFragment::new(line.as_str()).scale(Scale::uniform(*scale)));
+            let x_draw = (size as f32 - text.width(ctx) as f32) / 2.;
+
+            graphics::draw(ctx, &text, DrawParam::new().dest([x_draw, y_draw]))?;
+
+            y_draw += *scale + 16.;
+        }
+
+        Ok(())
+    }
+}
+
+impl EventHandler for RChess {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        self.run_computer();
+        self.run_tablebase();
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if !self.needs_draw {
+            return Ok(());
+        }
+        graphics::clear(ctx, Color::from_rgb(0, 0, 0));
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let x_sq = x as i32 * self.sq_size;
+                let y_sq = y as i32 * self.sq_size;
+
+                let r = graphics::Rect::new_i32(x_sq, y_sq, self.sq_size, self.sq_size);
+                let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), r, *cell)?;
+
+                graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+                let sq = Self::square_at(x as u8, y as u8);
+
+                if let Some(pc) = self.game.position().piece_at(sq) {
+                    let img = match self.pieces.get(&pc) {
+                        Some(i) => i,
+                        None => continue,
+                    };
+
+                    let ddraw = (self.sq_size as f32 - img.width() as f32 * 1.5) / 2.;
+                    let x_draw = x_sq as f32 + ddraw;
+                    let y_draw = y_sq as f32 + ddraw;
+                    let draw_param = DrawParam::new().dest([x_draw, y_draw]).scale([1.5, 1.5]);
+
+                    graphics::draw(ctx, img, draw_param)?;
+                }
+            }
+        }
+
+        let player = self.game.position().player();
+
+        for (sq, kind) in self.promotion_squares() {
+            let (x, y) = Self::coords(sq);
+            let x_sq = x as i32 * self.sq_size;
+            let y_sq = y as i32 * self.sq_size;
+
+            let r = graphics::Rect::new_i32(x_sq, y_sq, self.sq_size, self.sq_size);
+            let mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                r,
+                Color::from_rgb(240, 240, 240),
+            )?;
+
+            graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+            if let Some(img) = self.pieces.get(&Piece::new(player, kind)) {
+                let ddraw = (self.sq_size as f32 - img.width() as f32 * 1.5) / 2.;
+                let x_draw = x_sq as f32 + ddraw;
+                let y_draw = y_sq as f32 + ddraw;
+                let draw_param = DrawParam::new().dest([x_draw, y_draw]).scale([1.5, 1.5]);
+
+                graphics::draw(ctx, img, draw_param)?;
+            }
+        }
+
+        if let Some(outcome) = self.game.outcome() {
+            // A replayed game that ended stays on its last position
+            if self.replay.is_none() {
+                self.draw_game_over(ctx, outcome)?;
+            }
+        }
+
+        if self.show_book {
+            self.draw_book_moves(ctx)?;
+        }
+
+        self.draw_tablebase(ctx)?;
+
+        if let Some(text) = &self.entry {
+            self.draw_entry(ctx, text)?;
+        }
+
+        self.needs_draw = false;
+
+        graphics::present(ctx)
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, btn: MouseButton, x: f32, y: f32) {
+        let x = (x as i32 / self.sq_size) as u8;
+        let y = (y as i32 / self.sq_size) as u8;
+
+        if btn == MouseButton::Left && self.replay.is_none() {
+            if self.game.outcome().is_some() {
+                self.new_game();
+            } else if self.humans_turn() {
+                // Clicks on the board wait until the computer has moved
+                if self.promoting.is_some() {
+                    self.choose_promotion(x, y);
+                } else if !self.moving {
+                    self.select_piece(x, y);
+                } else {
+                    self.move_piece(x, y);
+                }
+            }
+        }
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, mods: KeyMods, _repeat: bool) {
+        let ctrl = mods.contains(KeyMods::CTRL);
+
+        // While a move is being typed in, letters are part of it
+        if self.entry.is_some() {
+            self.entry_key(key);
+            return;
+        }
+
+        let result = match key {
+            KeyCode::Escape => {
+                ggez::event::quit(ctx);
+                Ok(())
+            }
+            KeyCode::C if ctrl => self.copy_fen(),
+            KeyCode::V if ctrl => self.paste_fen(ctx),
+            KeyCode::S if ctrl => self.save_pgn(),
+            KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Return => {
+                self.replay_key(ctx, key);
+                Ok(())
+            }
+            KeyCode::Space if self.replay.is_none() && self.game.outcome().is_none() => {
+                self.entry = Some(String::new());
+                self.entry_error = None;
+                self.needs_draw = true;
+                Ok(())
+            }
+            KeyCode::D if self.replay.is_none() => {
+                self.claim_draw();
+                Ok(())
+            }
+            KeyCode::B => self.toggle_book(),
+            KeyCode::F => self.toggle_chess960(ctx),
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, ch: char) {
+        if let Some(text) = &mut self.entry {
+            if !ch.is_control() && !ch.is_whitespace() {
+                text.push(ch);
+                self.entry_error = None;
+                self.needs_draw = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! The rules of chess, without any user interface.
+//!
+//! [`Position`](position/struct.Position.html) holds a single position and
+//! knows how the pieces move, [`Game`](game/struct.Game.html) plays a whole
+//! game on top of it and decides when and how it ends. The `san`, `pgn` and
+//! `epd` modules read and write the usual chess notations, and `search`
+//! looks for good moves, with the help of an opening `book` and the endgame
+//! tables of `tablebase`.
+//!
+//! With the `serde` feature positions, moves and games can be serialized:
+//! positions as FEN and games as their moves in SAN.
+
+pub mod bitboard;
+pub mod book;
+pub mod epd;
+pub mod game;
+pub mod moves;
+pub mod pgn;
+pub mod piece;
+pub mod position;
+pub mod san;
+pub mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod square;
+pub mod tablebase;
+pub mod zobrist;
+
+pub use book::{Book, Selection};
+pub use epd::{Epd, EpdError};
+pub use game::{DrawReason, DrawRule, Game, IllegalMove, Outcome};
+pub use moves::{Move, MoveKind};
+pub use pgn::{PgnError, PgnGame, PgnMove, Variation};
+pub use piece::{Colour, Piece, PieceKind};
+pub use position::{CastlingNotation, FenError, InvalidCastling, InvalidPosition, Position, Undo};
+pub use san::ParseSanError;
+pub use search::{Engine, Limit, SearchResult};
+pub use square::{ParseSquareError, Square};
+pub use tablebase::{Tablebases, Wdl};
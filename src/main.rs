@@ -1,5 +1,5 @@
 extern crate ggez;
-mod game;
+mod gui;
 
 use ggez::{
     conf::{WindowMode, WindowSetup},
@@ -8,9 +8,107 @@ use ggez::{
 
 use std::path;
 
+use chess_rs::{pgn, Book, Colour, DrawRule, Engine, Position, Selection, Tablebases};
+
 const WIN_SIZE: u32 = 800;
 
 fn main() -> GameResult {
+    // --fen "<FEN>" starts from another position than the usual one
+    let args: Vec<String> = std::env::args().collect();
+    let position = match args.iter().position(|arg| arg == "--fen") {
+        Some(i) => match args.get(i + 1).map(|fen| Position::from_fen(fen)) {
+            Some(Ok(position)) => position,
+            Some(Err(e)) => {
+                eprintln!("Bad FEN: {}", e);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("--fen needs a FEN string");
+                std::process::exit(1);
+            }
+        },
+        None => Position::new(),
+    };
+
+    // --pgn <file> adds every finished game to the end of <file>
+    let pgn_path = match args.iter().position(|arg| arg == "--pgn") {
+        Some(i) => match args.get(i + 1) {
+            Some(file) => Some(path::PathBuf::from(file)),
+            None => {
+                eprintln!("--pgn needs a file name");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // --open <file> replays the games of a PGN file
+    let games = match args.iter().position(|arg| arg == "--open") {
+        Some(i) => match args.get(i + 1).map(std::fs::read_to_string) {
+            Some(Ok(text)) => match pgn::read(&text) {
+                Ok(games) => games,
+                Err(e) => {
+                    eprintln!("Bad PGN: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            Some(Err(e)) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("--open needs a file name");
+                std::process::exit(1);
+            }
+        },
+        None => Vec::new(),
+    };
+
+    // --book <file> opens a Polyglot book, --computer <white|black> lets the
+    // computer play one of the sides
+    let mut engine = Engine::new();
+
+    if let Some(i) = args.iter().position(|arg| arg == "--book") {
+        match args.get(i + 1).map(Book::open) {
+            Some(Ok(book)) => engine.set_book(book, Selection::Weighted),
+            Some(Err(e)) => {
+                eprintln!("Bad book: {}", e);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("--book needs a file name");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // --syzygy <dir> plays endgames from the Syzygy tables in a directory
+    if let Some(i) = args.iter().position(|arg| arg == "--syzygy") {
+        match args.get(i + 1).map(Tablebases::open) {
+            Some(Ok(tablebases)) => engine.set_tablebases(tablebases),
+            Some(Err(e)) => {
+                eprintln!("Bad tablebases: {}", e);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("--syzygy needs a directory");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let computer = match args.iter().position(|arg| arg == "--computer") {
+        Some(i) => match args.get(i + 1).map(String::as_str) {
+            Some("white") => Some(Colour::White),
+            Some("black") => Some(Colour::Black),
+            _ => {
+                eprintln!("--computer needs white or black");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
     let win_mode = WindowMode::default().dimensions(800., 800.);
 
     let win_setup = WindowSetup::default().title("Chess.rs");
@@ -26,7 +124,16 @@ fn main() -> GameResult {
         .build()
         .unwrap();
 
-    let mut game = game::RChess::new(&mut ctx)?;
+    // With --claim-draws the fifty-move and repetition draws have to be claimed
+    let draw_rule = if args.iter().any(|arg| arg == "--claim-draws") {
+        DrawRule::Claim
+    } else {
+        DrawRule::Automatic
+    };
+
+    let mut game = gui::RChess::new(&mut ctx, draw_rule, position, pgn_path)?;
+    game.set_engine(engine, computer);
+    game.replay(&ctx, games);
 
     event::run(&mut ctx, &mut event_loop, &mut game)
 }
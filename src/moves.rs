@@ -0,0 +1,80 @@
+use std::fmt;
+
+use crate::piece::{Piece, PieceKind};
+use crate::square::Square;
+
+/// What kind of move a [`Move`](struct.Move.html) is, for the moves that
+/// do more than take the piece from one square to another.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveKind {
+    Normal,
+    DoublePush,
+    EnPassant,
+    CastleKingSide,
+    CastleQueenSide,
+}
+
+/// A move of a single piece.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    /// The piece being moved.
+    pub piece: Piece,
+    /// The piece being taken, if any. For en passant this is the pawn
+    /// standing next to the moving pawn.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub captured: Option<Piece>,
+    /// What a pawn reaching the last rank turns into.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub promotion: Option<PieceKind>,
+    pub kind: MoveKind,
+}
+
+impl Move {
+    /// A `MoveKind::Normal` move without a promotion.
+    pub fn new(from: Square, to: Square, piece: Piece, captured: Option<Piece>) -> Self {
+        Self {
+            from,
+            to,
+            piece,
+            captured,
+            promotion: None,
+            kind: MoveKind::Normal,
+        }
+    }
+
+    /// Whether the move takes a piece.
+    pub fn is_capture(&self) -> bool {
+        self.captured.is_some()
+    }
+
+    /// Whether the move is a castling move.
+    pub fn is_castle(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::CastleKingSide | MoveKind::CastleQueenSide
+        )
+    }
+}
+
+/// Prints the move in coordinate notation, `e2e4` or `e7e8q`.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+
+        if let Some(kind) = self.promotion {
+            write!(f, "{}", kind.to_char().to_ascii_lowercase())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,523 @@
+//! Portable Game Notation, the text format games are stored and exchanged
+//! in.
+
+use std::error::Error;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::game::{DrawRule, Game, Outcome};
+use crate::moves::Move;
+use crate::piece::Colour;
+use crate::position::{FenError, Position};
+
+/* Movetext lines are kept below 80 characters, as the PGN export format
+ * asks.
+ */
+const LINE_LENGTH: usize = 79;
+
+/* The tags the game itself decides. `Result` is only taken from the tag
+ * pairs while the game isn't over.
+ */
+const DERIVED_TAGS: [&str; 3] = ["Result", "SetUp", "FEN"];
+
+/// A game read from PGN.
+#[derive(Clone)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appeared.
+    pub tags: Vec<(String, String)>,
+    /// The position from the `FEN` tag, or the standard starting position.
+    pub start: Position,
+    /// The moves of the game, with the variations hanging from them.
+    pub main_line: Variation,
+    /// The result at the end of the movetext, `1-0`, `0-1`, `1/2-1/2` or
+    /// `*`.
+    pub result: String,
+}
+
+/// A line of moves, either the main line of a game or a variation of it.
+#[derive(Clone, Default, Debug)]
+pub struct Variation {
+    /// The comments before the first move.
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+/// A move of a [`Variation`](struct.Variation.html), with the comments,
+/// annotations and alternatives that go with it.
+#[derive(Clone, Debug)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// The numeric annotation glyphs after the move. `!`, `?`, `!!`, `??`,
+    /// `!?` and `?!` are read as the glyphs 1 to 6.
+    pub nags: Vec<u8>,
+    /// The comments after the move.
+    pub comments: Vec<String>,
+    /// Lines played instead of this move.
+    pub variations: Vec<Variation>,
+}
+
+/// Returned by [`read`](fn.read.html) for text that isn't valid PGN.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PgnError {
+    /// A tag pair that isn't written `[Name "value"]`.
+    Tag,
+    /// The `FEN` tag doesn't hold a valid position.
+    Fen(FenError),
+    /// A move that isn't legal where it's played, given as it was written.
+    Move(String),
+    /// A variation that doesn't follow a move, or a `)` without a `(`.
+    Variation,
+    /// A comment or variation that never ends.
+    Unclosed,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Tag => write!(f, "malformed tag pair"),
+            Self::Fen(e) => write!(f, "bad FEN tag: {}", e),
+            Self::Move(san) => write!(f, "illegal move {}", san),
+            Self::Variation => write!(f, "misplaced variation"),
+            Self::Unclosed => write!(f, "unclosed comment or variation"),
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+impl PgnGame {
+    /// The value of the tag `name`, if the game has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The game after the first `plies` moves of the main line, with the
+    /// tags of the PGN. Moves after the game has ended under `draw_rule`
+    /// are left out.
+    pub fn to_game(&self, plies: usize, draw_rule: DrawRule) -> Game {
+        let mut game = Game::from_position(self.start.clone(), draw_rule);
+
+        for (name, value) in &self.tags {
+            if name != "SetUp" && name != "FEN" {
+                game.set_tag(name, value);
+            }
+        }
+
+        // A result given only after the moves still counts
+        if self.result != "*" && self.tag("Result").is_none() {
+            game.set_tag("Result", &self.result);
+        }
+
+        for pgn_move in self.main_line.moves.iter().take(plies) {
+            if game.play(&pgn_move.mv).is_err() {
+                break;
+            }
+        }
+
+        game
+    }
+}
+
+impl Game {
+    /// Writes the game in the PGN export format: the tag pairs in the
+    /// order they were added, with `Result` after `Black`, then `SetUp` and
+    /// `FEN` if the game didn't start from the standard position, and the
+    /// moves in standard algebraic notation followed by the result.
+    pub fn to_pgn(&self) -> String {
+        let result = match self.outcome() {
+            Some(_) => result(self.outcome()),
+            None => self.tag("Result").unwrap_or("*"),
+        };
+        let mut pgn = String::new();
+
+        for (name, value) in self.tags() {
+            if DERIVED_TAGS.contains(&name.as_str()) {
+                continue;
+            }
+
+            pgn.push_str(&tag_pair(name, value));
+
+            if name == "Black" {
+                pgn.push_str(&tag_pair("Result", result));
+            }
+        }
+
+        if self.tag("Black").is_none() {
+            pgn.push_str(&tag_pair("Result", result));
+        }
+
+        let start = self.start();
+
+        if start.is_chess960() && self.tag("Variant").is_none() {
+            pgn.push_str(&tag_pair("Variant", "Chess960"));
+        }
+
+        if start.to_fen() != Position::new().to_fen() {
+            pgn.push_str(&tag_pair("SetUp", "1"));
+            pgn.push_str(&tag_pair("FEN", &start.to_fen()));
+        }
+
+        pgn.push('\n');
+
+        let mut line = String::new();
+
+        for token in self.movetext(result) {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            line.push_str(&token);
+        }
+
+        pgn.push_str(&line);
+        pgn.push('\n');
+
+        pgn
+    }
+
+    /* The move numbers, moves and result that make up the movetext, one
+     * token each.
+     */
+    fn movetext(&self, result: &str) -> Vec<String> {
+        let start = self.start();
+        let black_first = start.player() == Colour::Black;
+        let mut tokens = Vec::new();
+
+        for (i, san) in self.san_moves().into_iter().enumerate() {
+            let ply = i + black_first as usize;
+            let number = start.fullmove() as usize + ply / 2;
+
+            if ply % 2 == 0 {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", number));
+            }
+
+            tokens.push(san);
+        }
+
+        tokens.push(result.to_string());
+        tokens
+    }
+}
+
+/// The PGN result of a game: `1-0`, `0-1`, `1/2-1/2`, or `*` while it's
+/// still going on.
+pub fn result(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::WhiteWins) => "1-0",
+        Some(Outcome::BlackWins) => "0-1",
+        Some(Outcome::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// The date of `time` as the `Date` tag writes it, like `2020.06.21`.
+pub fn date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    // Counts in 400-year eras starting on the 1st of March, 0000, so that
+    // the leap day falls at the end of each year
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Reads every game in `text`. Each move is checked against the position
+/// it's played in, variations included.
+pub fn read(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    let mut games = Vec::new();
+
+    while tokens.peek().is_some() {
+        let mut tags = Vec::new();
+
+        while let Some(Token::Tag(name, value)) = tokens.peek() {
+            tags.push((name.clone(), value.clone()));
+            tokens.next();
+        }
+
+        let start = match tags.iter().find(|(name, _)| name == "FEN") {
+            Some((_, fen)) => Position::from_fen(fen).map_err(PgnError::Fen)?,
+            None => Position::new(),
+        };
+
+        let main_line = read_variation(&mut tokens, start.clone(), false)?;
+
+        let result = match tokens.peek() {
+            Some(Token::Result(result)) => {
+                let result = result.clone();
+                tokens.next();
+                result
+            }
+            // A game without a result is ended by the tags of the next one
+            _ => tags
+                .iter()
+                .find(|(name, _)| name == "Result")
+                .map_or_else(|| String::from("*"), |(_, value)| value.clone()),
+        };
+
+        games.push(PgnGame {
+            tags,
+            start,
+            main_line,
+            result,
+        });
+    }
+
+    Ok(games)
+}
+
+/* The pieces PGN text is made of.
+ */
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    San(String),
+}
+
+/* Reads the moves of a line starting from `position`, up to the result or
+ * the tags of the next game, or up to the `)` closing it when it's a
+ * variation.
+ */
+fn read_variation<I>(
+    tokens: &mut Peekable<I>,
+    mut position: Position,
+    nested: bool,
+) -> Result<Variation, PgnError>
+where
+    I: Iterator<Item = Token>,
+{
+    let mut line = Variation::default();
+    // The position before the last move, where its variations start
+    let mut before = position.clone();
+
+    loop {
+        match tokens.peek() {
+            None | Some(Token::Tag(..)) | Some(Token::Result(_)) if nested => {
+                return Err(PgnError::Unclosed)
+            }
+            None | Some(Token::Tag(..)) | Some(Token::Result(_)) => return Ok(line),
+            _ => (),
+        }
+
+        match tokens.next() {
+            Some(Token::San(san)) => {
+                let mv = position.parse_san(&san).map_err(|_| PgnError::Move(san))?;
+
+                before = position.clone();
+                position.move_piece(&mv);
+
+                line.moves.push(PgnMove {
+                    mv,
+                    nags: Vec::new(),
+                    comments: Vec::new(),
+                    variations: Vec::new(),
+                });
+            }
+            Some(Token::Comment(comment)) => match line.moves.last_mut() {
+                Some(last) => last.comments.push(comment),
+                None => line.comments.push(comment),
+            },
+            Some(Token::Nag(nag)) => {
+                if let Some(last) = line.moves.last_mut() {
+                    last.nags.push(nag);
+                }
+            }
+            Some(Token::Open) => {
+                let variation = read_variation(tokens, before.clone(), true)?;
+
+                match line.moves.last_mut() {
+                    Some(last) => last.variations.push(variation),
+                    None => return Err(PgnError::Variation),
+                }
+            }
+            Some(Token::Close) if nested => return Ok(line),
+            _ => return Err(PgnError::Variation),
+        }
+    }
+}
+
+/* Splits PGN text into tokens, leaving out move numbers and escaped lines.
+ */
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut chars = text.chars().peekable();
+    let mut tokens = Vec::new();
+    let mut line_start = true;
+
+    while let Some(ch) = chars.next() {
+        let at_line_start = line_start;
+        line_start = ch == '\n';
+
+        match ch {
+            // Lines starting with % are meant for other programs
+            '%' if at_line_start => {
+                chars.by_ref().find(|ch| *ch == '\n');
+                line_start = true;
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|ch| *ch != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+            }
+            '{' => {
+                let mut comment = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => comment.push(ch),
+                        None => return Err(PgnError::Unclosed),
+                    }
+                }
+
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '$' => {
+                if let Ok(nag) = read_symbol(&mut chars, String::new()).parse() {
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+            ch if ch.is_whitespace() => (),
+            ch => {
+                let symbol = read_symbol(&mut chars, ch.to_string());
+                push_symbol(&mut tokens, &symbol);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/* Reads the rest of a tag pair after its `[`.
+ */
+fn read_tag(chars: &mut Peekable<Chars>) -> Result<Token, PgnError> {
+    while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+        chars.next();
+    }
+
+    let name = read_symbol(chars, String::new());
+
+    while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+        chars.next();
+    }
+
+    if name.is_empty() || chars.next() != Some('"') {
+        return Err(PgnError::Tag);
+    }
+
+    let mut value = String::new();
+
+    loop {
+        match chars.next() {
+            Some('\\') => value.extend(chars.next()),
+            Some('"') => break,
+            Some(ch) => value.push(ch),
+            None => return Err(PgnError::Tag),
+        }
+    }
+
+    while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+        chars.next();
+    }
+
+    match chars.next() {
+        Some(']') => Ok(Token::Tag(name, value)),
+        _ => Err(PgnError::Tag),
+    }
+}
+
+/* Reads up to the next space or character with a meaning of its own,
+ * starting with `symbol`.
+ */
+fn read_symbol(chars: &mut Peekable<Chars>, mut symbol: String) -> String {
+    while let Some(ch) = chars.peek() {
+        if ch.is_whitespace() || "{}()[];$\"".contains(*ch) {
+            break;
+        }
+
+        symbol.push(*ch);
+        chars.next();
+    }
+
+    symbol
+}
+
+/* Turns a symbol into a result, or a move and its annotation, dropping
+ * the move number in front of it.
+ */
+fn push_symbol(tokens: &mut Vec<Token>, symbol: &str) {
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&symbol) {
+        tokens.push(Token::Result(symbol.to_string()));
+        return;
+    }
+
+    // Move numbers end in dots, unlike castling written as `0-0`
+    let number = symbol.trim_start_matches(|ch: char| ch.is_ascii_digit());
+    let san = if number.is_empty() || number.starts_with('.') {
+        number.trim_start_matches('.')
+    } else {
+        symbol
+    };
+
+    if san.is_empty() {
+        return;
+    }
+
+    let annotation = san.trim_start_matches(|ch| !matches!(ch, '!' | '?'));
+    let san = &san[..san.len() - annotation.len()];
+
+    if !san.is_empty() {
+        tokens.push(Token::San(san.to_string()));
+    }
+
+    let nag = match annotation {
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        "?!" => 6,
+        _ => return,
+    };
+
+    tokens.push(Token::Nag(nag));
+}
+
+/* A tag pair line, with quotes and backslashes in the value escaped.
+ */
+fn tag_pair(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("[{} \"{}\"]\n", name, value)
+}
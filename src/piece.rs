@@ -0,0 +1,144 @@
+//! The two sides and the pieces they play with.
+
+use std::fmt;
+
+/// One of the two sides.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Colour {
+    White,
+    Black,
+}
+
+impl Colour {
+    /// Both sides, white first.
+    pub const ALL: [Colour; 2] = [Colour::White, Colour::Black];
+
+    /// Returns the other side.
+    pub fn switch(self) -> Self {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+
+    /* 0 for white and 1 for black, for tables kept per colour.
+     */
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let to_print = match *self {
+            Self::White => "White",
+            Self::Black => "Black",
+        };
+
+        write!(f, "{}", to_print)
+    }
+}
+
+/// What a piece is, regardless of its colour.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind {
+    /// Every kind of piece, pawn first.
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    /// The pieces a pawn can promote to, strongest first.
+    pub const PROMOTIONS: [PieceKind; 4] = [
+        PieceKind::Queen,
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Knight,
+    ];
+
+    /// Reads the letter of a piece, `P`, `N`, `B`, `R`, `Q` or `K` in
+    /// either case.
+    pub fn from_char(ch: char) -> Option<Self> {
+        match ch.to_ascii_uppercase() {
+            'P' => Some(Self::Pawn),
+            'N' => Some(Self::Knight),
+            'B' => Some(Self::Bishop),
+            'R' => Some(Self::Rook),
+            'Q' => Some(Self::Queen),
+            'K' => Some(Self::King),
+            _ => None,
+        }
+    }
+
+    /// The upper case letter of the piece.
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Pawn => 'P',
+            Self::Knight => 'N',
+            Self::Bishop => 'B',
+            Self::Rook => 'R',
+            Self::Queen => 'Q',
+            Self::King => 'K',
+        }
+    }
+
+    /* The position of the kind in `ALL`, for tables kept per kind.
+     */
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A piece of one of the sides.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Piece {
+    pub colour: Colour,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    pub const fn new(colour: Colour, kind: PieceKind) -> Self {
+        Self { colour, kind }
+    }
+
+    /// Reads a piece the way FEN writes it, upper case for white and lower
+    /// case for black.
+    pub fn from_char(ch: char) -> Option<Self> {
+        let colour = if ch.is_ascii_uppercase() {
+            Colour::White
+        } else {
+            Colour::Black
+        };
+
+        PieceKind::from_char(ch).map(|kind| Self::new(colour, kind))
+    }
+
+    /// The FEN letter of the piece.
+    pub fn to_char(self) -> char {
+        match self.colour {
+            Colour::White => self.kind.to_char(),
+            Colour::Black => self.kind.to_char().to_ascii_lowercase(),
+        }
+    }
+}
+
+/// Prints the FEN letter of the piece.
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
@@ -0,0 +1,1610 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+use std::vec::Vec;
+
+use crate::bitboard::{self, Bitboard, Squares, FILE_A, FILE_H};
+use crate::game::{DrawReason, Outcome};
+use crate::moves::{Move, MoveKind};
+use crate::piece::{Colour, Piece, PieceKind};
+use crate::square::Square;
+use crate::zobrist;
+
+/// A chess position: the pieces on the board, the side to move, castling
+/// rights, en passant possibilities and the move counters.
+///
+/// The castling rights are a bitmask, `0b1000` for white queen side,
+/// `0b0100` white king side, `0b0010` black queen side and `0b0001` black
+/// king side. En passant files are bitmasks too, `0x80 >> x` is set in
+/// `enp_w` (or `enp_b`) right after a white (or black) pawn on file `x`
+/// made a double step.
+///
+/// Every castling right also remembers the file of its rook, which is
+/// always the a- or h-file in standard chess but can be any file in
+/// Chess960. Castling ends with king and rook on the usual squares either
+/// way: the king on the c- or g-file and the rook next to it.
+///
+/// The pieces are stored twice, as one entry per square for looking up
+/// what stands where, and as one [`Bitboard`](../bitboard/type.Bitboard.html)
+/// per piece kind and per colour for generating moves.
+pub struct Position {
+    board: [Option<Piece>; 64],
+    pieces: [Bitboard; 6],
+    colours: [Bitboard; 2],
+    player: Colour,
+    enp_b: u8,
+    enp_w: u8,
+    castling: u8,
+    castling_files: [u8; 4],
+    chess960: bool,
+    halfmove: u16,
+    fullmove: u16,
+    hash: u64,
+}
+
+/// What [`Position::make_move`](struct.Position.html#method.make_move)
+/// overwrote, for [`Position::unmake_move`](struct.Position.html#method.unmake_move)
+/// to restore.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Undo {
+    /// The piece taken by the move, if any.
+    pub captured: Option<Piece>,
+    pub castling: u8,
+    pub enp_b: u8,
+    pub enp_w: u8,
+    pub halfmove: u16,
+    pub fullmove: u16,
+    pub hash: u64,
+}
+
+/// How the castling rights are written in FEN.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CastlingNotation {
+    /// `KQkq`, except that a right is written with the file of its rook
+    /// when another rook stands further out on the same side (X-FEN).
+    XFen,
+    /// Always the files of the rooks, like `HAha` (Shredder-FEN).
+    Shredder,
+}
+
+/// Returned when castling rights don't fit the position they are set on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct InvalidCastling;
+
+impl fmt::Display for InvalidCastling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid castling rights")
+    }
+}
+
+impl Error for InvalidCastling {}
+
+/// Something that makes a position impossible to reach in a game, found by
+/// [`Position::validate`](struct.Position.html#method.validate).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InvalidPosition {
+    /// A side has this many kings instead of one.
+    KingCount(Colour, u32),
+    /// A pawn stands on the first or last rank.
+    PawnOnBackRank(Square),
+    /// The side that just moved left its king in check.
+    OpponentInCheck,
+    /// A side may castle without its king and rook on their squares.
+    CastlingRights(Colour),
+    /// The en passant file doesn't belong to a double step just made.
+    EnPassant,
+    /// A side has more than 8 pawns.
+    TooManyPawns(Colour),
+    /// A side has more pieces than its pawns could have promoted to, or
+    /// more than 16 in all.
+    TooManyPieces(Colour),
+}
+
+impl fmt::Display for InvalidPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::KingCount(colour, count) => write!(f, "{} has {} kings", colour, count),
+            Self::PawnOnBackRank(sq) => write!(f, "pawn on {}", sq),
+            Self::OpponentInCheck => write!(f, "the side not to move is in check"),
+            Self::CastlingRights(colour) => write!(f, "{} can't castle like that", colour),
+            Self::EnPassant => write!(f, "impossible en passant square"),
+            Self::TooManyPawns(colour) => write!(f, "{} has too many pawns", colour),
+            Self::TooManyPieces(colour) => write!(f, "{} has too many pieces", colour),
+        }
+    }
+}
+
+impl Error for InvalidPosition {}
+
+/// Returned when a FEN string can't be read, saying which field is wrong.
+#[derive(Clone, PartialEq, Debug)]
+pub enum FenError {
+    /// The piece placement isn't 8 ranks of 8 squares of known pieces.
+    Placement,
+    /// The side to move isn't `w` or `b`.
+    SideToMove,
+    /// The castling rights don't fit the pieces, see
+    /// [`InvalidCastling`](struct.InvalidCastling.html).
+    Castling,
+    /// The en passant square isn't on the 3rd or 6th rank behind a pawn of
+    /// the side that just moved.
+    EnPassant,
+    /// One of the move counters isn't a number.
+    Counter,
+    /// Every field reads fine but the position isn't possible.
+    Invalid(Vec<InvalidPosition>),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Placement => write!(f, "invalid piece placement"),
+            Self::SideToMove => write!(f, "invalid side to move"),
+            Self::Castling => write!(f, "invalid castling rights"),
+            Self::EnPassant => write!(f, "invalid en passant square"),
+            Self::Counter => write!(f, "invalid move counter"),
+            Self::Invalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "invalid position: {}", errors.join(", "))
+            }
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl std::clone::Clone for Position {
+    fn clone(&self) -> Self {
+        Self {
+            board: self.board,
+            pieces: self.pieces,
+            colours: self.colours,
+            player: self.player,
+            enp_b: self.enp_b,
+            enp_w: self.enp_w,
+            castling: self.castling,
+            castling_files: self.castling_files,
+            chess960: self.chess960,
+            halfmove: self.halfmove,
+            fullmove: self.fullmove,
+            hash: self.hash,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::from("[\n");
+
+        for rank in (0..8).rev() {
+            let row: Vec<char> = (0..8)
+                .map(|file| self.board[Square::new(file, rank).index()].map_or('-', Piece::to_char))
+                .collect();
+
+            out.push_str(&format!("\t{:?}\n", row));
+        }
+
+        out.push(']');
+
+        write!(f, "{}", out)
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Position {
+    /// Returns the standard starting position.
+    pub fn new() -> Self {
+        use PieceKind::*;
+
+        let row = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+
+        Self::from_board(Self::starting_board(row), Colour::White, 0b1111)
+    }
+
+    /// Sets up a position with the pieces on `board`, indexed by
+    /// [`Square::index`](../square/struct.Square.html#method.index). There
+    /// is no en passant capture to begin with and the move counters start
+    /// over. Nothing is checked, see [`validate`](#method.validate) for
+    /// that.
+    pub fn from_board(board: [Option<Piece>; 64], player: Colour, castling: u8) -> Self {
+        let mut position = Self {
+            board: [None; 64],
+            pieces: [0; 6],
+            colours: [0; 2],
+            player,
+            enp_b: 0,
+            enp_w: 0,
+            castling,
+            castling_files: [0, 7, 0, 7],
+            chess960: false,
+            halfmove: 0,
+            fullmove: 1,
+            hash: 0,
+        };
+
+        for (i, pc) in board.iter().enumerate() {
+            if let Some(pc) = pc {
+                position.put_piece(*pc, Square::from_index(i));
+            }
+        }
+
+        position.hash = position.zobrist_hash();
+        position
+    }
+
+    /// Returns Chess960 starting position number `index`, counted the usual
+    /// way (Scharnagl's numbering), so that 518 is the standard starting
+    /// position. Indices above 959 wrap around.
+    pub fn chess960(index: u16) -> Self {
+        let mut n = (index % 960) as usize;
+        let mut row = [None; 8];
+
+        // The bishops go on opposite colours, then the queen and the knights
+        // on the free squares left, and the rooks and king on the last three
+        row[n % 4 * 2 + 1] = Some(PieceKind::Bishop);
+        n /= 4;
+        row[n % 4 * 2] = Some(PieceKind::Bishop);
+        n /= 4;
+
+        let free = |row: &[Option<PieceKind>; 8], i: usize| {
+            (0..8).filter(|x| row[*x].is_none()).nth(i).unwrap()
+        };
+
+        let queen = free(&row, n % 6);
+        row[queen] = Some(PieceKind::Queen);
+        n /= 6;
+
+        let knights = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+
+        let (first, second) = knights[n];
+        let (first, second) = (free(&row, first), free(&row, second));
+        row[first] = Some(PieceKind::Knight);
+        row[second] = Some(PieceKind::Knight);
+
+        let (q_rook, king, k_rook) = (free(&row, 0), free(&row, 1), free(&row, 2));
+        row[q_rook] = Some(PieceKind::Rook);
+        row[king] = Some(PieceKind::King);
+        row[k_rook] = Some(PieceKind::Rook);
+
+        let board = Self::starting_board(row.map(Option::unwrap));
+        let mut position = Self::from_board(board, Colour::White, 0b1111);
+        position.castling_files = [q_rook as u8, k_rook as u8, q_rook as u8, k_rook as u8];
+        position.chess960 = true;
+        position
+    }
+
+    /// Returns one of the 960 Chess960 starting positions at random.
+    pub fn random_chess960() -> Self {
+        let index = RandomState::new().build_hasher().finish() % 960;
+
+        Self::chess960(index as u16)
+    }
+
+    /// Reads a position from FEN. Castling rights can be written in X-FEN or
+    /// Shredder-FEN as well, and the two move counters can be left out like
+    /// in EPD. The position also has to pass [`validate`](#method.validate).
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next().ok_or(FenError::Placement)?;
+        let ranks: Vec<&str> = placement.split('/').collect();
+        let mut board = [None; 64];
+
+        if ranks.len() != 8 {
+            return Err(FenError::Placement);
+        }
+
+        for (y, row) in ranks.iter().enumerate() {
+            let rank = 7 - y as u8;
+            let mut file = 0;
+
+            for ch in row.chars() {
+                if file >= 8 {
+                    return Err(FenError::Placement);
+                }
+
+                match ch.to_digit(10) {
+                    Some(n @ 1..=8) => file += n as u8,
+                    _ => {
+                        let pc = Piece::from_char(ch).ok_or(FenError::Placement)?;
+                        board[Square::new(file, rank).index()] = Some(pc);
+                        file += 1;
+                    }
+                }
+            }
+
+            if file != 8 {
+                return Err(FenError::Placement);
+            }
+        }
+
+        let player = match fields.next() {
+            Some("w") => Colour::White,
+            Some("b") => Colour::Black,
+            _ => return Err(FenError::SideToMove),
+        };
+
+        let mut position = Self::from_board(board, player, 0);
+
+        let castling = fields.next().ok_or(FenError::Castling)?;
+        position
+            .set_castling_rights(castling)
+            .map_err(|_| FenError::Castling)?;
+
+        match fields.next() {
+            Some("-") => (),
+            Some(name) => {
+                let sq: Square = name.parse().map_err(|_| FenError::EnPassant)?;
+                let enp = 0x80 >> sq.file();
+
+                match (player, sq.rank()) {
+                    (Colour::White, 5) => position.enp_b = enp,
+                    (Colour::Black, 2) => position.enp_w = enp,
+                    _ => return Err(FenError::EnPassant),
+                }
+            }
+            None => return Err(FenError::EnPassant),
+        }
+
+        if let Some(halfmove) = fields.next() {
+            position.halfmove = halfmove.parse().map_err(|_| FenError::Counter)?;
+        }
+
+        if let Some(fullmove) = fields.next() {
+            position.fullmove = fullmove.parse().map_err(|_| FenError::Counter)?;
+        }
+
+        position.hash = position.zobrist_hash();
+        position.validate().map_err(FenError::Invalid)?;
+
+        Ok(position)
+    }
+
+    /// Writes the position as FEN, with the castling rights in X-FEN. The
+    /// en passant square is given after every double step, whether or not
+    /// a pawn can take there.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                match self.piece_at(Square::new(file, rank)) {
+                    Some(pc) => {
+                        if empty > 0 {
+                            fen.push((b'0' + empty) as char);
+                            empty = 0;
+                        }
+
+                        fen.push(pc.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen.push((b'0' + empty) as char);
+            }
+
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        let (side, enp, enp_rank) = match self.player {
+            Colour::White => ('w', self.enp_b, 5),
+            Colour::Black => ('b', self.enp_w, 2),
+        };
+
+        let enp = if enp == 0 {
+            String::from("-")
+        } else {
+            Square::new(enp.leading_zeros() as u8, enp_rank).to_string()
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            fen,
+            side,
+            self.castling_rights(CastlingNotation::XFen),
+            enp,
+            self.halfmove,
+            self.fullmove
+        )
+    }
+
+    /// Whether this is a Chess960 game, where castling has to be told apart
+    /// by the rook rather than the square the king lands on.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// The squares of the board, indexed by
+    /// [`Square::index`](../square/struct.Square.html#method.index).
+    pub fn board(&self) -> &[Option<Piece>; 64] {
+        &self.board
+    }
+
+    /// The piece on `sq`, if any.
+    pub fn piece_at(&self, sq: Square) -> Option<Piece> {
+        self.board[sq.index()]
+    }
+
+    /// The squares holding the piece `pc`.
+    pub fn pieces(&self, pc: Piece) -> Bitboard {
+        self.pieces[pc.kind.index()] & self.colours[pc.colour.index()]
+    }
+
+    /// The squares holding one of `plyr`'s pieces.
+    pub fn colour(&self, plyr: Colour) -> Bitboard {
+        self.colours[plyr.index()]
+    }
+
+    /// The squares holding any piece.
+    pub fn occupied(&self) -> Bitboard {
+        self.colours[0] | self.colours[1]
+    }
+
+    /// The Zobrist key of the position, the same as the one Polyglot opening
+    /// books use. Positions that are the same for the repetition rules have
+    /// the same key.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The side to move.
+    pub fn player(&self) -> Colour {
+        self.player
+    }
+
+    /// The castling rights bitmask.
+    pub fn castling(&self) -> u8 {
+        self.castling
+    }
+
+    /// Writes the castling rights the way FEN does, `-` if there are none.
+    pub fn castling_rights(&self, notation: CastlingNotation) -> String {
+        let mut rights = String::new();
+
+        for (i, letter) in [(1, 'K'), (0, 'Q'), (3, 'k'), (2, 'q')].iter() {
+            if self.castling & (0b1000 >> i) == 0 {
+                continue;
+            }
+
+            let file = self.castling_files[*i];
+            let colour = if *i < 2 { Colour::White } else { Colour::Black };
+            let rank = Self::back_rank(colour);
+            let rook = Some(Piece::new(colour, PieceKind::Rook));
+            let is_rook = |x| self.piece_at(Square::new(x, rank)) == rook;
+
+            // X-FEN only needs the file if K or Q would pick another rook
+            let outermost = if i % 2 == 1 {
+                !(file + 1..8).any(is_rook)
+            } else {
+                !(0..file).any(is_rook)
+            };
+
+            let ch = match notation {
+                CastlingNotation::XFen if outermost => *letter,
+                _ => {
+                    let file = (b'A' + file) as char;
+
+                    match colour {
+                        Colour::White => file,
+                        Colour::Black => file.to_ascii_lowercase(),
+                    }
+                }
+            };
+
+            rights.push(ch);
+        }
+
+        if rights.is_empty() {
+            rights.push('-');
+        }
+
+        rights
+    }
+
+    /// Replaces the castling rights with ones written in X-FEN or
+    /// Shredder-FEN, which includes plain `KQkq`. Every right needs the king
+    /// and a rook on their side's back rank, and `K`/`Q` pick the rook
+    /// furthest out. Rights that don't fit the standard setup make this a
+    /// Chess960 position.
+    pub fn set_castling_rights(&mut self, rights: &str) -> Result<(), InvalidCastling> {
+        let mut castling = 0;
+        let mut files = [0, 7, 0, 7];
+
+        for ch in rights.chars().filter(|ch| *ch != '-') {
+            let (side, colour) = if ch.is_ascii_uppercase() {
+                (0, Colour::White)
+            } else {
+                (2, Colour::Black)
+            };
+
+            let rank = Self::back_rank(colour);
+            let king = Some(Piece::new(colour, PieceKind::King));
+            let rook = Some(Piece::new(colour, PieceKind::Rook));
+
+            let king_x = match (0..8).find(|x| self.piece_at(Square::new(*x, rank)) == king) {
+                Some(x) => x,
+                None => return Err(InvalidCastling),
+            };
+
+            let mut rooks = (0..8).filter(|x| self.piece_at(Square::new(*x, rank)) == rook);
+
+            let rook_x = match ch.to_ascii_uppercase() {
+                'K' => rooks.rfind(|x| *x > king_x),
+                'Q' => rooks.find(|x| *x < king_x),
+                file @ 'A'..='H' => rooks.find(|x| *x == file as u8 - b'A'),
+                _ => None,
+            };
+
+            let rook_x = match rook_x {
+                Some(x) if x != king_x => x,
+                _ => return Err(InvalidCastling),
+            };
+
+            let i = if rook_x > king_x { side + 1 } else { side };
+
+            castling |= 0b1000 >> i;
+            files[i] = rook_x;
+
+            if king_x != 4 || (rook_x != 0 && rook_x != 7) {
+                self.chess960 = true;
+            }
+        }
+
+        self.castling = castling;
+        self.castling_files = files;
+        self.hash = self.zobrist_hash();
+
+        Ok(())
+    }
+
+    /// The square of the rook that castles along with `mv`, or `None` if
+    /// `mv` isn't a castling move.
+    pub fn castling_rook(&self, mv: &Move) -> Option<Square> {
+        let side = match mv.piece.colour {
+            Colour::White => 0,
+            Colour::Black => 2,
+        };
+
+        let i = match mv.kind {
+            MoveKind::CastleQueenSide => side,
+            MoveKind::CastleKingSide => side + 1,
+            _ => return None,
+        };
+
+        Some(Square::new(self.castling_files[i], mv.from.rank()))
+    }
+
+    /// The en passant files opened up by white's last move.
+    pub fn enp_w(&self) -> u8 {
+        self.enp_w
+    }
+
+    /// The en passant files opened up by black's last move.
+    pub fn enp_b(&self) -> u8 {
+        self.enp_b
+    }
+
+    /// Number of halfmoves since the last capture or pawn move.
+    pub fn halfmove(&self) -> u16 {
+        self.halfmove
+    }
+
+    /// The move number, starting at 1 and going up after every black move.
+    pub fn fullmove(&self) -> u16 {
+        self.fullmove
+    }
+
+    /// Whether `plyr`'s king is in check.
+    pub fn in_check(&self, plyr: Colour) -> bool {
+        let king = self.pieces(Piece::new(plyr, PieceKind::King));
+
+        king != 0
+            && self.attacked(
+                Square::from_index(king.trailing_zeros() as usize),
+                plyr.switch(),
+                self.occupied(),
+            )
+    }
+
+    /// Checks whether any of `by`'s pieces attacks `sq`, no matter what
+    /// stands there.
+    pub fn is_square_attacked(&self, sq: Square, by: Colour) -> bool {
+        self.attacked(sq, by, self.occupied())
+    }
+
+    /// The squares `plyr`'s pieces attack, whether or not a move there
+    /// would be legal.
+    pub fn attacks_by(&self, plyr: Colour) -> Bitboard {
+        let own = self.colour(plyr);
+        let occupied = self.occupied();
+        let pawns = self.kind(PieceKind::Pawn) & own;
+
+        let mut attacks = match plyr {
+            Colour::White => ((pawns << 7) & !FILE_H) | ((pawns << 9) & !FILE_A),
+            Colour::Black => ((pawns >> 9) & !FILE_H) | ((pawns >> 7) & !FILE_A),
+        };
+
+        for sq in Squares(self.kind(PieceKind::Knight) & own) {
+            attacks |= bitboard::knight_attacks(sq);
+        }
+
+        for sq in Squares((self.kind(PieceKind::Bishop) | self.kind(PieceKind::Queen)) & own) {
+            attacks |= bitboard::bishop_attacks(sq, occupied);
+        }
+
+        for sq in Squares((self.kind(PieceKind::Rook) | self.kind(PieceKind::Queen)) & own) {
+            attacks |= bitboard::rook_attacks(sq, occupied);
+        }
+
+        for sq in Squares(self.kind(PieceKind::King) & own) {
+            attacks |= bitboard::king_attacks(sq);
+        }
+
+        attacks
+    }
+
+    /// Returns every move of the side to move that follows the movement
+    /// rules of the pieces, including the ones that leave the mover's own
+    /// king in check. Castling through check is never generated.
+    pub fn pseudo_legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::<Move>::with_capacity(64);
+
+        for sq in Squares(self.colour(self.player)) {
+            self.get_piece_moves(sq, !0, &mut moves);
+        }
+
+        moves
+    }
+
+    /// Returns every legal move of the side to move.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::<Move>::with_capacity(64);
+        self.push_legal_moves(self.colour(self.player), &mut moves);
+
+        moves
+    }
+
+    /// Returns the legal moves that take a piece, en passant included.
+    pub fn captures(&self) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.retain(|mv| mv.is_capture());
+
+        moves
+    }
+
+    /// Returns the legal moves that don't take anything. Promotions without
+    /// a capture count as quiet moves.
+    pub fn quiet_moves(&self) -> Vec<Move> {
+        let mut moves = self.legal_moves();
+        moves.retain(|mv| !mv.is_capture());
+
+        moves
+    }
+
+    /// The pieces giving check to the side to move.
+    pub fn checkers(&self) -> Bitboard {
+        match self.king(self.player) {
+            Some(king) => self.attackers(king, self.player.switch(), self.occupied()),
+            None => 0,
+        }
+    }
+
+    /// The pieces of the side to move that can't leave the line between
+    /// their king and an enemy bishop, rook or queen without exposing the
+    /// king.
+    pub fn pinned(&self) -> Bitboard {
+        let king = match self.king(self.player) {
+            Some(king) => king,
+            None => return 0,
+        };
+
+        let them = self.colour(self.player.switch());
+        let diagonal = self.kind(PieceKind::Bishop) | self.kind(PieceKind::Queen);
+        let straight = self.kind(PieceKind::Rook) | self.kind(PieceKind::Queen);
+
+        // Sliders that would see the king through an empty board
+        let snipers = ((bitboard::bishop_attacks(king, 0) & diagonal)
+            | (bitboard::rook_attacks(king, 0) & straight))
+            & them;
+
+        let mut pinned = 0;
+
+        for sniper in Squares(snipers) {
+            let blockers = bitboard::between(king, sniper) & self.occupied();
+
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & self.colour(self.player);
+            }
+        }
+
+        pinned
+    }
+
+    /// Checks whether a pseudo-legal move keeps the mover's king out of check.
+    pub fn is_legal(&self, mv: &Move) -> bool {
+        self.clone().keeps_king_safe(mv)
+    }
+
+    /// Returns the legal moves of the piece on `sq`. Empty squares and
+    /// pieces of the side not to move have no moves.
+    pub fn piece_moves(&self, sq: Square) -> Vec<Move> {
+        match self.piece_at(sq) {
+            Some(pc) if pc.colour == self.player => (),
+            _ => return Vec::new(),
+        }
+
+        let mut moves = Vec::<Move>::new();
+        self.push_legal_moves(bitboard::bit(sq), &mut moves);
+
+        moves
+    }
+
+    /// Looks up the legal move `from` -> `to`. `promote` picks the piece a
+    /// pawn reaching the last rank turns into, a queen if it's `None`, and
+    /// is ignored for every other move.
+    pub fn find_move(&self, from: Square, to: Square, promote: Option<PieceKind>) -> Option<Move> {
+        let promote = promote.unwrap_or(PieceKind::Queen);
+
+        self.piece_moves(from)
+            .into_iter()
+            .find(|mv| mv.to == to && (mv.promotion.is_none() || mv.promotion == Some(promote)))
+    }
+
+    /// Plays `mv` for the side to move and hands the turn over. The move is
+    /// not checked for legality, see [`legal_moves`](#method.legal_moves)
+    /// for that.
+    pub fn move_piece(&mut self, mv: &Move) {
+        self.make_move(mv);
+    }
+
+    /// Plays `mv` like [`move_piece`](#method.move_piece) and returns what
+    /// [`unmake_move`](#method.unmake_move) needs to take it back.
+    pub fn make_move(&mut self, mv: &Move) -> Undo {
+        // In Chess960 the king can land on its own rook's square
+        let captured = match mv.kind {
+            MoveKind::EnPassant => self.piece_at(Self::en_passant_victim(mv)),
+            _ if mv.is_castle() => None,
+            _ => self.piece_at(mv.to),
+        };
+
+        let undo = Undo {
+            captured,
+            castling: self.castling,
+            enp_b: self.enp_b,
+            enp_w: self.enp_w,
+            halfmove: self.halfmove,
+            fullmove: self.fullmove,
+            hash: self.hash,
+        };
+
+        self.hash ^= self.enp_hash();
+        self.move_piece_to(mv);
+        self.player = self.player.switch();
+        self.hash ^= zobrist::castling(undo.castling ^ self.castling);
+        self.hash ^= zobrist::white_to_move();
+        self.hash ^= self.enp_hash();
+
+        debug_assert_eq!(self.hash, self.zobrist_hash());
+
+        undo
+    }
+
+    /// Takes back `mv`, which has to be the last move made with
+    /// [`make_move`](#method.make_move), `undo` being what it returned.
+    pub fn unmake_move(&mut self, mv: &Move, undo: Undo) {
+        self.player = self.player.switch();
+        self.remove_piece(mv.to);
+
+        // The rook has to go back before the king in case it ended up on
+        // the king's square
+        if let Some(rook) = self.castling_rook(mv) {
+            self.remove_piece(Self::castled_rook_square(mv, rook));
+            self.put_piece(Piece::new(mv.piece.colour, PieceKind::Rook), rook);
+        }
+
+        self.put_piece(mv.piece, mv.from);
+
+        if let Some(pc) = undo.captured {
+            match mv.kind {
+                MoveKind::EnPassant => self.put_piece(pc, Self::en_passant_victim(mv)),
+                _ => self.put_piece(pc, mv.to),
+            }
+        }
+
+        self.castling = undo.castling;
+        self.enp_b = undo.enp_b;
+        self.enp_w = undo.enp_w;
+        self.halfmove = undo.halfmove;
+        self.fullmove = undo.fullmove;
+        self.hash = undo.hash;
+    }
+
+    /// Counts the leaf nodes of the tree of legal moves `depth` plies deep.
+    /// Comparing the counts with published ones is the usual way of
+    /// testing a move generator.
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().count_nodes(depth)
+    }
+
+    /// Splits [`perft`](#method.perft) up by the first move, which narrows
+    /// a wrong count down to the move it goes wrong after.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut state = self.clone();
+
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = state.make_move(&mv);
+                let nodes = state.count_nodes(depth - 1);
+                state.unmake_move(&mv, undo);
+
+                (mv, nodes)
+            })
+            .collect()
+    }
+
+    /// Checks whether the side to move has at least one legal move.
+    pub fn has_legal_moves(&self) -> bool {
+        !self.legal_moves().is_empty()
+    }
+
+    /// Returns the outcome if the side to move has no legal moves left:
+    /// checkmate if their king is in check and stalemate otherwise.
+    pub fn check_for_game_over(&self) -> Option<Outcome> {
+        if self.has_legal_moves() {
+            return None;
+        }
+
+        if self.in_check(self.player) {
+            Some(Outcome::lost(self.player))
+        } else {
+            Some(Outcome::Draw(DrawReason::Stalemate))
+        }
+    }
+
+    /// Checks whether `plyr` has enough material left to checkmate with
+    /// some series of legal moves, with help from the opponent if need be.
+    pub fn has_mating_material(&self, plyr: Colour) -> bool {
+        // A pawn, rook or queen is always enough and a bare king never is.
+        // A lone knight needs an enemy piece to hem the king in, and bishops
+        // that all stand on one colour need an enemy piece that can stand
+        // on the other one.
+        let mut knights = 0;
+        let mut bishops = [false; 2];
+        let mut enemy_bishops = [false; 2];
+        let mut enemy_others = 0;
+
+        for (i, pc) in self.board.iter().enumerate() {
+            let pc = match pc {
+                Some(pc) => pc,
+                None => continue,
+            };
+
+            let sq = Square::from_index(i);
+            let shade = ((sq.file() + sq.rank()) % 2) as usize;
+
+            if pc.colour != plyr {
+                match pc.kind {
+                    PieceKind::King => (),
+                    PieceKind::Bishop => enemy_bishops[shade] = true,
+                    _ => enemy_others += 1,
+                }
+
+                continue;
+            }
+
+            match pc.kind {
+                PieceKind::Pawn | PieceKind::Rook | PieceKind::Queen => return true,
+                PieceKind::Knight => knights += 1,
+                PieceKind::Bishop => bishops[shade] = true,
+                PieceKind::King => (),
+            }
+        }
+
+        match (knights, bishops) {
+            (0, [false, false]) => false,
+            (0, [true, false]) => enemy_others > 0 || enemy_bishops[1],
+            (0, [false, true]) => enemy_others > 0 || enemy_bishops[0],
+            (1, [false, false]) => enemy_others > 0 || enemy_bishops != [false, false],
+            _ => true,
+        }
+    }
+
+    /// A position is dead when neither player can possibly checkmate.
+    pub fn is_dead(&self) -> bool {
+        !self.has_mating_material(Colour::White) && !self.has_mating_material(Colour::Black)
+    }
+
+    /// Checks that the position could come up in a game, returning every
+    /// problem found if it couldn't. Move generation counts on exactly one
+    /// king per side, so positions that fail this shouldn't be played on.
+    pub fn validate(&self) -> Result<(), Vec<InvalidPosition>> {
+        let mut errors = Vec::new();
+
+        for colour in Colour::ALL.iter() {
+            let count = |kind| self.pieces(Piece::new(*colour, kind)).count_ones();
+            let kings = count(PieceKind::King);
+
+            if kings != 1 {
+                errors.push(InvalidPosition::KingCount(*colour, kings));
+            }
+
+            // Every piece past the starting set has to be a promoted pawn
+            let pawns = count(PieceKind::Pawn);
+            let promoted: u32 = [
+                (PieceKind::Knight, 2),
+                (PieceKind::Bishop, 2),
+                (PieceKind::Rook, 2),
+                (PieceKind::Queen, 1),
+            ]
+            .iter()
+            .map(|(kind, start)| count(*kind).saturating_sub(*start))
+            .sum();
+
+            if pawns > 8 {
+                errors.push(InvalidPosition::TooManyPawns(*colour));
+            }
+
+            if self.colour(*colour).count_ones() > 16 || pawns + promoted > 8 {
+                errors.push(InvalidPosition::TooManyPieces(*colour));
+            }
+        }
+
+        for sq in Squares(self.kind(PieceKind::Pawn) & (bitboard::RANK_1 | bitboard::RANK_8)) {
+            errors.push(InvalidPosition::PawnOnBackRank(sq));
+        }
+
+        if self.in_check(self.player.switch()) {
+            errors.push(InvalidPosition::OpponentInCheck);
+        }
+
+        for colour in Colour::ALL.iter() {
+            if !self.castling_fits(*colour) {
+                errors.push(InvalidPosition::CastlingRights(*colour));
+            }
+        }
+
+        if !self.en_passant_fits() {
+            errors.push(InvalidPosition::EnPassant);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /* Whether every castling right of `plyr` has the king on the back rank
+     * with the rook on the right side of it, and in standard chess on the
+     * usual squares.
+     */
+    fn castling_fits(&self, plyr: Colour) -> bool {
+        let side = plyr.index() * 2;
+        let rank = Self::back_rank(plyr);
+        let king = self.king(plyr).filter(|king| king.rank() == rank);
+        let rook = Some(Piece::new(plyr, PieceKind::Rook));
+
+        (side..side + 2).all(|i| {
+            if self.castling & (0b1000 >> i) == 0 {
+                return true;
+            }
+
+            let king = match king {
+                Some(king) => king,
+                None => return false,
+            };
+
+            let file = self.castling_files[i];
+            let rook_side = if i % 2 == 1 {
+                file > king.file()
+            } else {
+                file < king.file()
+            };
+
+            rook_side
+                && self.piece_at(Square::new(file, rank)) == rook
+                && (self.chess960 || (king.file() == 4 && (file == 0 || file == 7)))
+        })
+    }
+
+    /* Whether an en passant file, if there is one, comes from a pawn of the
+     * side that just moved that has stepped over an empty square.
+     */
+    fn en_passant_fits(&self) -> bool {
+        let (enp, other, pawn) = match self.player {
+            Colour::White => (
+                self.enp_b,
+                self.enp_w,
+                Piece::new(Colour::Black, PieceKind::Pawn),
+            ),
+            Colour::Black => (
+                self.enp_w,
+                self.enp_b,
+                Piece::new(Colour::White, PieceKind::Pawn),
+            ),
+        };
+
+        if enp == 0 && other == 0 {
+            return true;
+        }
+
+        if other != 0 || enp.count_ones() != 1 {
+            return false;
+        }
+
+        let file = enp.leading_zeros() as u8;
+        let (from, over, to) = match self.player {
+            Colour::White => (6, 5, 4),
+            Colour::Black => (1, 2, 3),
+        };
+
+        self.piece_at(Square::new(file, to)) == Some(pawn)
+            && self.piece_at(Square::new(file, over)).is_none()
+            && self.piece_at(Square::new(file, from)).is_none()
+    }
+
+    /* The board of a starting position with `row` on the back ranks.
+     */
+    fn starting_board(row: [PieceKind; 8]) -> [Option<Piece>; 64] {
+        let mut board = [None; 64];
+
+        for (x, kind) in row.iter().enumerate() {
+            let x = x as u8;
+
+            board[Square::new(x, 0).index()] = Some(Piece::new(Colour::White, *kind));
+            board[Square::new(x, 1).index()] = Some(Piece::new(Colour::White, PieceKind::Pawn));
+            board[Square::new(x, 6).index()] = Some(Piece::new(Colour::Black, PieceKind::Pawn));
+            board[Square::new(x, 7).index()] = Some(Piece::new(Colour::Black, *kind));
+        }
+
+        board
+    }
+
+    /* The rank the pieces of `plyr` start on.
+     */
+    fn back_rank(plyr: Colour) -> u8 {
+        match plyr {
+            Colour::White => 0,
+            Colour::Black => 7,
+        }
+    }
+
+    /* Applies a move and all its side effects (castling rook hops,
+     * en passant captures, promotions, castling rights and clocks).
+     * A pawn reaching the last rank without a promotion piece becomes a
+     * queen. The side to move is left alone.
+     */
+    fn move_piece_to(&mut self, mv: &Move) {
+        let mut pc = mv.piece;
+
+        self.remove_piece(mv.from);
+        self.enp_b = 0;
+        self.enp_w = 0;
+
+        // The halfmove clock only runs while no pawn moves and nothing is taken
+        if pc.kind == PieceKind::Pawn || mv.is_capture() {
+            self.halfmove = 0;
+        } else {
+            self.halfmove += 1;
+        }
+
+        if pc.colour == Colour::Black {
+            self.fullmove += 1;
+        }
+
+        // Moving a castling rook or capturing it takes away its castling right
+        for (i, file) in self.castling_files.iter().enumerate() {
+            let colour = if i < 2 { Colour::White } else { Colour::Black };
+            let home = Square::new(*file, Self::back_rank(colour));
+
+            if mv.from == home || mv.to == home {
+                self.castling &= !(0b1000 >> i);
+            }
+        }
+
+        match pc.kind {
+            PieceKind::King => match pc.colour {
+                Colour::White => self.castling &= 0b0011,
+                Colour::Black => self.castling &= 0b1100,
+            },
+
+            PieceKind::Pawn => {
+                if mv.to.rank() == Self::back_rank(pc.colour.switch()) {
+                    pc.kind = mv.promotion.unwrap_or(PieceKind::Queen);
+                } else if mv.kind == MoveKind::DoublePush {
+                    match pc.colour {
+                        Colour::White => self.enp_w = 0x80 >> mv.from.file(),
+                        Colour::Black => self.enp_b = 0x80 >> mv.from.file(),
+                    }
+                }
+            }
+
+            _ => (),
+        }
+
+        if let Some(rook) = self.castling_rook(mv) {
+            self.remove_piece(rook);
+            self.put_piece(
+                Piece::new(pc.colour, PieceKind::Rook),
+                Self::castled_rook_square(mv, rook),
+            );
+        } else if mv.kind == MoveKind::EnPassant {
+            self.remove_piece(Self::en_passant_victim(mv));
+        }
+
+        self.remove_piece(mv.to);
+        self.put_piece(pc, mv.to);
+    }
+
+    /* The square the rook on `rook` ends up on after castling with `mv`.
+     */
+    fn castled_rook_square(mv: &Move, rook: Square) -> Square {
+        let file = match mv.kind {
+            MoveKind::CastleQueenSide => 3,
+            _ => 5,
+        };
+
+        Square::new(file, rook.rank())
+    }
+
+    /* The square of the pawn taken by an en passant move.
+     */
+    fn en_passant_victim(mv: &Move) -> Square {
+        Square::new(mv.to.file(), mv.from.rank())
+    }
+
+    /* The recursive part of `perft`, making and unmaking the moves in place.
+     */
+    fn count_nodes(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = self.legal_moves();
+
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+
+        for mv in &moves {
+            let undo = self.make_move(mv);
+            nodes += self.count_nodes(depth - 1);
+            self.unmake_move(mv, undo);
+        }
+
+        nodes
+    }
+
+    /* Plays a pseudo-legal move, sees whether it left the mover's king in
+     * check and takes it back again.
+     */
+    fn keeps_king_safe(&mut self, mv: &Move) -> bool {
+        // A king move only has to land on a square that isn't attacked once
+        // the king is out of the way
+        if mv.piece.kind == PieceKind::King {
+            let occupied = self.occupied() & !bitboard::bit(mv.from);
+
+            return !self.attacked(mv.to, self.player.switch(), occupied);
+        }
+
+        let undo = self.make_move(mv);
+        let safe = !self.in_check(self.player.switch());
+        self.unmake_move(mv, undo);
+
+        safe
+    }
+
+    /* Pushes the legal moves of the side to move's pieces in `from`.
+     *
+     * Rather than trying every move out, the moves are held to a mask of
+     * squares they may end on. In check, that's the checking piece and the
+     * squares in between, or nothing at all in double check, where only
+     * the king can move. A pinned piece also has to stay on the line of
+     * its pin, and the king can only go where nothing attacks it once it's
+     * out of the way.
+     */
+    fn push_legal_moves(&self, from: Bitboard, moves: &mut Vec<Move>) {
+        let king = match self.king(self.player) {
+            Some(king) => king,
+            None => {
+                for sq in Squares(from) {
+                    self.get_piece_moves(sq, !0, moves);
+                }
+
+                return;
+            }
+        };
+
+        let checkers = self.checkers();
+        let pinned = self.pinned();
+
+        let evasions = match checkers.count_ones() {
+            0 => !0,
+            1 => {
+                checkers
+                    | bitboard::between(
+                        king,
+                        Square::from_index(checkers.trailing_zeros() as usize),
+                    )
+            }
+            _ => 0,
+        };
+
+        for sq in Squares(from & !bitboard::bit(king)) {
+            let mut mask = evasions;
+
+            if pinned & bitboard::bit(sq) != 0 {
+                mask &= bitboard::line(king, sq);
+            }
+
+            self.get_piece_moves(sq, mask, moves);
+        }
+
+        if from & bitboard::bit(king) != 0 {
+            let opponent = self.player.switch();
+            let occupied = self.occupied() & !bitboard::bit(king);
+            let safe = Squares(bitboard::king_attacks(king))
+                .filter(|sq| !self.attacked(*sq, opponent, occupied))
+                .fold(0, |safe, sq| safe | bitboard::bit(sq));
+
+            self.get_piece_moves(king, safe, moves);
+        }
+
+        // Taking en passant empties two squares on the pawn's rank, which
+        // can open it up to a rook or queen even if neither pawn was pinned
+        // on its own, so those moves are checked against the board they
+        // leave behind
+        moves.retain(|mv| mv.kind != MoveKind::EnPassant || self.en_passant_is_safe(mv, king));
+    }
+
+    /* Checks whether the king on `king` is out of check after the en
+     * passant capture `mv`.
+     */
+    fn en_passant_is_safe(&self, mv: &Move, king: Square) -> bool {
+        let victim = bitboard::bit(Self::en_passant_victim(mv));
+        let occupied = (self.occupied() & !bitboard::bit(mv.from) & !victim) | bitboard::bit(mv.to);
+
+        self.attackers(king, self.player.switch(), occupied) & !victim == 0
+    }
+
+    /* The square of `plyr`'s king, if there is one.
+     */
+    fn king(&self, plyr: Colour) -> Option<Square> {
+        Squares(self.pieces(Piece::new(plyr, PieceKind::King))).next()
+    }
+
+    /* Checks whether one of `by`'s pieces attacks `sq`, with sliders
+     * blocked by the squares in `occupied`.
+     */
+    fn attacked(&self, sq: Square, by: Colour, occupied: Bitboard) -> bool {
+        self.attackers(sq, by, occupied) != 0
+    }
+
+    /* Looks outward from `sq` along the ray, knight, pawn and king patterns
+     * for `by`'s pieces attacking it. Sliders are blocked by the squares in
+     * `occupied`.
+     */
+    fn attackers(&self, sq: Square, by: Colour, occupied: Bitboard) -> Bitboard {
+        // A white pawn attacks the squares a black pawn there would attack
+        // from, and the other way round
+        let diagonal = self.kind(PieceKind::Bishop) | self.kind(PieceKind::Queen);
+        let straight = self.kind(PieceKind::Rook) | self.kind(PieceKind::Queen);
+
+        let attackers = (bitboard::pawn_attacks(by.switch(), sq) & self.kind(PieceKind::Pawn))
+            | (bitboard::knight_attacks(sq) & self.kind(PieceKind::Knight))
+            | (bitboard::king_attacks(sq) & self.kind(PieceKind::King))
+            | (bitboard::bishop_attacks(sq, occupied) & diagonal)
+            | (bitboard::rook_attacks(sq, occupied) & straight);
+
+        attackers & self.colour(by)
+    }
+
+    /* Works out the hash of the position from scratch.
+     */
+    fn zobrist_hash(&self) -> u64 {
+        let mut hash = zobrist::castling(self.castling) ^ self.enp_hash();
+
+        for (i, pc) in self.board.iter().enumerate() {
+            if let Some(pc) = pc {
+                hash ^= zobrist::piece(*pc, Square::from_index(i));
+            }
+        }
+
+        if self.player == Colour::White {
+            hash ^= zobrist::white_to_move();
+        }
+
+        hash
+    }
+
+    /* The en passant part of the hash. Like in Polyglot, the file of a
+     * double step only counts if a pawn of the side to move stands right
+     * next to the pawn that made it.
+     */
+    fn enp_hash(&self) -> u64 {
+        let (enp, rank) = match self.player {
+            Colour::White => (self.enp_b, 4),
+            Colour::Black => (self.enp_w, 3),
+        };
+
+        if enp == 0 {
+            return 0;
+        }
+
+        let x = enp.leading_zeros() as u8;
+        let pawns = self.pieces(Piece::new(self.player, PieceKind::Pawn));
+        let pushed = bitboard::bit(Square::new(x, rank));
+        let beside = ((pushed << 1) & !FILE_A) | ((pushed >> 1) & !FILE_H);
+
+        if pawns & beside != 0 {
+            zobrist::en_passant(x)
+        } else {
+            0
+        }
+    }
+
+    /* The squares holding a piece of `kind`, of either colour.
+     */
+    fn kind(&self, kind: PieceKind) -> Bitboard {
+        self.pieces[kind.index()]
+    }
+
+    /* Puts a piece on an empty square, on the board and the bitboards alike.
+     */
+    fn put_piece(&mut self, pc: Piece, sq: Square) {
+        let b = bitboard::bit(sq);
+
+        self.board[sq.index()] = Some(pc);
+        self.hash ^= zobrist::piece(pc, sq);
+        self.pieces[pc.kind.index()] |= b;
+        self.colours[pc.colour.index()] |= b;
+    }
+
+    /* Empties a square, if there is anything on it.
+     */
+    fn remove_piece(&mut self, sq: Square) {
+        if let Some(pc) = self.board[sq.index()].take() {
+            let b = bitboard::bit(sq);
+
+            self.hash ^= zobrist::piece(pc, sq);
+            self.pieces[pc.kind.index()] &= !b;
+            self.colours[pc.colour.index()] &= !b;
+        }
+    }
+
+    /* Adds a move for `pc` from `from` to every square in `targets` that
+     * doesn't hold one of the mover's own pieces.
+     */
+    fn push_moves(&self, moves: &mut Vec<Move>, pc: Piece, from: Square, targets: Bitboard) {
+        for to in Squares(targets & !self.colour(pc.colour)) {
+            moves.push(Move::new(from, to, pc, self.piece_at(to)));
+        }
+    }
+
+    /* Adds a pawn move to the list. A move to the last rank is added
+     * once for every piece the pawn can promote to.
+     */
+    fn push_pawn_move(
+        &self,
+        moves: &mut Vec<Move>,
+        pc: Piece,
+        from: Square,
+        to: Square,
+        kind: MoveKind,
+    ) {
+        let mut mv = Move::new(from, to, pc, self.piece_at(to));
+        mv.kind = kind;
+
+        if kind == MoveKind::EnPassant {
+            mv.captured = self.piece_at(Self::en_passant_victim(&mv));
+        }
+
+        if to.rank() == 0 || to.rank() == 7 {
+            for promotion in PieceKind::PROMOTIONS.iter() {
+                mv.promotion = Some(*promotion);
+                moves.push(mv);
+            }
+        } else {
+            moves.push(mv);
+        }
+    }
+
+    /* Pushes all the moves the pawn on `sq` can make: single and double
+     * steps forward, captures and en passant. En passant is allowed if it
+     * either lands in `mask` or takes a pawn in it.
+     */
+    fn mv_pawn(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        let (dy, home_rank, enp_rank, enp) = match pc.colour {
+            Colour::White => (1, 1, 4, self.enp_b),
+            Colour::Black => (-1, 6, 3, self.enp_w),
+        };
+
+        let to = match sq.offset(0, dy) {
+            Some(to) => to,
+            None => return,
+        };
+
+        if self.piece_at(to).is_none() {
+            if mask & bitboard::bit(to) != 0 {
+                self.push_pawn_move(moves, pc, sq, to, MoveKind::Normal);
+            }
+
+            if sq.rank() == home_rank {
+                let double = Square::new(sq.file(), (to.rank() as i8 + dy) as u8);
+
+                if self.piece_at(double).is_none() && mask & bitboard::bit(double) != 0 {
+                    self.push_pawn_move(moves, pc, sq, double, MoveKind::DoublePush);
+                }
+            }
+        }
+
+        let attacks = bitboard::pawn_attacks(pc.colour, sq);
+
+        for target in Squares(attacks & self.colour(pc.colour.switch()) & mask) {
+            self.push_pawn_move(moves, pc, sq, target, MoveKind::Normal);
+        }
+
+        if sq.rank() == enp_rank && enp != 0 {
+            let target = Square::new(enp.leading_zeros() as u8, to.rank());
+            let victim = Square::new(target.file(), sq.rank());
+
+            if attacks & bitboard::bit(target) != 0
+                && mask & (bitboard::bit(target) | bitboard::bit(victim)) != 0
+            {
+                self.push_pawn_move(moves, pc, sq, target, MoveKind::EnPassant);
+            }
+        }
+    }
+
+    /* Used for moving a knight. The jumps come from a precomputed table.
+     */
+    fn mv_knight(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        self.push_moves(moves, pc, sq, bitboard::knight_attacks(sq) & mask);
+    }
+
+    /* Used for moving a bishop
+     */
+    fn mv_bishop(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        self.push_moves(
+            moves,
+            pc,
+            sq,
+            bitboard::bishop_attacks(sq, self.occupied()) & mask,
+        );
+    }
+
+    /* Used for moving a Rook
+     */
+    fn mv_rook(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        self.push_moves(
+            moves,
+            pc,
+            sq,
+            bitboard::rook_attacks(sq, self.occupied()) & mask,
+        );
+    }
+
+    /* Used for moving a Queen
+     */
+    fn mv_queen(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        self.push_moves(
+            moves,
+            pc,
+            sq,
+            bitboard::queen_attacks(sq, self.occupied()) & mask,
+        );
+    }
+
+    /* Used for moving a King. Castling needs the squares between king and
+     * rook to be empty and the king's path to be out of the opponent's reach,
+     * so it doesn't go through `mask` like the king's other moves.
+     */
+    fn mv_king(&self, pc: Piece, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        self.push_moves(moves, pc, sq, bitboard::king_attacks(sq) & mask);
+
+        let side = match pc.colour {
+            Colour::White => 0,
+            Colour::Black => 2,
+        };
+
+        if self.castling & (0b1100 >> side) == 0 {
+            return;
+        }
+
+        let opponent = pc.colour.switch();
+        let occupied = self.occupied();
+
+        if self.attacked(sq, opponent, occupied) {
+            return;
+        }
+
+        let rook = Some(Piece::new(pc.colour, PieceKind::Rook));
+
+        let sides = [
+            (side, MoveKind::CastleQueenSide, 2),
+            (side + 1, MoveKind::CastleKingSide, 6),
+        ];
+
+        for (i, kind, king_x) in sides.iter() {
+            let rook_sq = Square::new(self.castling_files[*i], sq.rank());
+
+            if self.castling & (0b1000 >> i) == 0 || self.piece_at(rook_sq) != rook {
+                continue;
+            }
+
+            let mut mv = Move::new(sq, Square::new(*king_x, sq.rank()), pc, None);
+            mv.kind = *kind;
+
+            // Both pieces need a clear way to their squares, and the king
+            // mustn't pass through check. The castling rook might be
+            // shielding one of those squares, so it's taken off the board.
+            let others = occupied & !bitboard::bit(sq) & !bitboard::bit(rook_sq);
+            let king_path = Self::row_span(sq, mv.to);
+            let rook_path = Self::row_span(rook_sq, Self::castled_rook_square(&mv, rook_sq));
+
+            if others & (king_path | rook_path) == 0
+                && !Squares(king_path).any(|sq| self.attacked(sq, opponent, others))
+            {
+                moves.push(mv);
+            }
+        }
+    }
+
+    /* The squares from `a` to `b` on the same rank, both included.
+     */
+    fn row_span(a: Square, b: Square) -> Bitboard {
+        let (first, last) = if a < b { (a, b) } else { (b, a) };
+
+        (first.file()..=last.file())
+            .fold(0, |span, x| span | bitboard::bit(Square::new(x, a.rank())))
+    }
+
+    /* Pushes all possible moves for the piece on `sq` that end on a square
+     * in `mask`.
+     */
+    fn get_piece_moves(&self, sq: Square, mask: Bitboard, moves: &mut Vec<Move>) {
+        let pc = match self.piece_at(sq) {
+            Some(pc) => pc,
+            None => return,
+        };
+
+        match pc.kind {
+            PieceKind::Pawn => self.mv_pawn(pc, sq, mask, moves),
+            PieceKind::Rook => self.mv_rook(pc, sq, mask, moves),
+            PieceKind::Knight => self.mv_knight(pc, sq, mask, moves),
+            PieceKind::Bishop => self.mv_bishop(pc, sq, mask, moves),
+            PieceKind::Queen => self.mv_queen(pc, sq, mask, moves),
+            PieceKind::King => self.mv_king(pc, sq, mask, moves),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Standard algebraic notation, the way moves are written in PGN.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::moves::{Move, MoveKind};
+use crate::piece::PieceKind;
+use crate::position::Position;
+use crate::square::Square;
+
+/// Why a string couldn't be read as one of the legal moves of a position.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ParseSanError {
+    /// The string isn't written like a move at all.
+    Invalid,
+    /// No legal move fits the string.
+    Illegal,
+    /// More than one legal move fits the string, like `Nd2` with knights on
+    /// b1 and f3.
+    Ambiguous,
+}
+
+impl fmt::Display for ParseSanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let to_print = match *self {
+            Self::Invalid => "not a move",
+            Self::Illegal => "illegal move",
+            Self::Ambiguous => "ambiguous move",
+        };
+
+        write!(f, "{}", to_print)
+    }
+}
+
+impl Error for ParseSanError {}
+
+impl Position {
+    /// Writes `mv`, which has to be legal in this position, in standard
+    /// algebraic notation: `e4`, `Nbd7`, `exd6`, `e8=Q+`, `O-O-O#` and
+    /// so on. The origin of a piece is only given when another piece of the
+    /// same kind could move to the same square.
+    pub fn san(&self, mv: &Move) -> String {
+        let mut san = match mv.kind {
+            MoveKind::CastleKingSide => String::from("O-O"),
+            MoveKind::CastleQueenSide => String::from("O-O-O"),
+            _ => self.san_without_check(mv),
+        };
+
+        let mut after = self.clone();
+        after.move_piece(mv);
+
+        if after.in_check(after.player()) {
+            san.push(if after.has_legal_moves() { '+' } else { '#' });
+        }
+
+        san
+    }
+
+    /// Finds the legal move written as `san`. Besides proper SAN this
+    /// takes the ways moves are often written by hand or by other programs:
+    /// a missing or extra origin (`Ng1f3`, `e2e4`), a missing `x` or `=`
+    /// (`ed6`, `e8Q`), pawn captures by their files alone (`ed`), `0-0`
+    /// for castling, `e.p.` after en passant, and check signs and
+    /// annotations that are wrong or left out.
+    pub fn parse_san(&self, san: &str) -> Result<Move, ParseSanError> {
+        let pattern = Pattern::read(san)?;
+        let mut fits = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| pattern.fits(self, mv));
+
+        match (fits.next(), fits.next()) {
+            (Some(mv), None) => Ok(mv),
+            (None, _) => Err(ParseSanError::Illegal),
+            _ => Err(ParseSanError::Ambiguous),
+        }
+    }
+
+    /* Everything but the check or mate sign, for moves other than castling.
+     */
+    fn san_without_check(&self, mv: &Move) -> String {
+        let mut san = String::new();
+        let from = mv.from.to_string();
+
+        if mv.piece.kind == PieceKind::Pawn {
+            if mv.is_capture() {
+                san.push_str(&from[..1]);
+            }
+        } else {
+            san.push(mv.piece.kind.to_char());
+
+            let rivals: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.piece == mv.piece
+                        && other.to == mv.to
+                        && other.from != mv.from
+                        && !other.is_castle()
+                })
+                .collect();
+
+            if !rivals.is_empty() {
+                if rivals
+                    .iter()
+                    .all(|other| other.from.file() != mv.from.file())
+                {
+                    san.push_str(&from[..1]);
+                } else if rivals
+                    .iter()
+                    .all(|other| other.from.rank() != mv.from.rank())
+                {
+                    san.push_str(&from[1..]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+
+        if mv.is_capture() {
+            san.push('x');
+        }
+
+        san.push_str(&mv.to.to_string());
+
+        if let Some(kind) = mv.promotion {
+            san.push('=');
+            san.push(kind.to_char());
+        }
+
+        san
+    }
+}
+
+/* What a move written in algebraic notation says about the move: the
+ * side it castles to, or the piece, the squares and the promotion. Parts
+ * left out of the notation are `None`.
+ */
+struct Pattern {
+    castle: Option<MoveKind>,
+    kind: Option<PieceKind>,
+    from_file: Option<u8>,
+    from_rank: Option<u8>,
+    to_file: u8,
+    to_rank: Option<u8>,
+    promotion: Option<PieceKind>,
+}
+
+impl Pattern {
+    /* Picks `san` apart, ignoring check signs, annotations and the
+     * separators between squares.
+     */
+    fn read(san: &str) -> Result<Self, ParseSanError> {
+        let san = san.trim().trim_end_matches(&['+', '#', '!', '?'][..]);
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+
+        let castle = match san.replace(&['0', 'o'][..], "O").as_str() {
+            "O-O" => Some(MoveKind::CastleKingSide),
+            "O-O-O" => Some(MoveKind::CastleQueenSide),
+            _ => None,
+        };
+
+        if castle.is_some() {
+            return Ok(Self {
+                castle,
+                kind: None,
+                from_file: None,
+                from_rank: None,
+                to_file: 0,
+                to_rank: None,
+                promotion: None,
+            });
+        }
+
+        let mut chars: Vec<char> = san
+            .chars()
+            .filter(|ch| !matches!(ch, 'x' | ':' | '-' | '=' | '(' | ')' | '/'))
+            .collect();
+
+        // A lower case b is the b-file, never a bishop
+        let kind = match chars.first() {
+            Some(ch) if ch.is_ascii_uppercase() || "nrqk".contains(*ch) => {
+                let kind = PieceKind::from_char(*ch).ok_or(ParseSanError::Invalid)?;
+                chars.remove(0);
+                Some(kind)
+            }
+            _ => None,
+        };
+
+        let promotion = match chars.as_slice() {
+            [.., '1'..='8', ch] if ch.is_ascii_alphabetic() => {
+                let kind = PieceKind::from_char(*ch).ok_or(ParseSanError::Invalid)?;
+                chars.pop();
+                Some(kind)
+            }
+            _ => None,
+        };
+
+        let file = |ch: char| ('a'..='h').contains(&ch).then(|| ch as u8 - b'a');
+        let rank = |ch: char| ('1'..='8').contains(&ch).then(|| ch as u8 - b'1');
+
+        // Pawn captures written with the two files only, like `ed`
+        if let (None, [from, to]) = (kind, chars.as_slice()) {
+            if let (Some(from), Some(to)) = (file(*from), file(*to)) {
+                return Ok(Self {
+                    castle: None,
+                    kind: Some(PieceKind::Pawn),
+                    from_file: Some(from),
+                    from_rank: None,
+                    to_file: to,
+                    to_rank: None,
+                    promotion,
+                });
+            }
+        }
+
+        let (origin, to) = match chars.len() {
+            2..=4 => chars.split_at(chars.len() - 2),
+            _ => return Err(ParseSanError::Invalid),
+        };
+
+        let (to_file, to_rank) = match (file(to[0]), rank(to[1])) {
+            (Some(f), Some(r)) => (f, r),
+            _ => return Err(ParseSanError::Invalid),
+        };
+
+        let (from_file, from_rank) = match *origin {
+            [] => (None, None),
+            [ch] if file(ch).is_some() => (file(ch), None),
+            [ch] if rank(ch).is_some() => (None, rank(ch)),
+            [f, r] if file(f).is_some() && rank(r).is_some() => (file(f), rank(r)),
+            _ => return Err(ParseSanError::Invalid),
+        };
+
+        Ok(Self {
+            castle: None,
+            kind,
+            from_file,
+            from_rank,
+            to_file,
+            to_rank: Some(to_rank),
+            promotion,
+        })
+    }
+
+    /* Whether `mv`, a legal move of `position`, fits the pattern. Without a
+     * piece letter the move is a pawn move, unless the whole origin square
+     * is given as in `g1f3`. A pawn reaching the last rank fits whatever
+     * it promotes to if the piece is left out.
+     *
+     * Castling can be given by squares as well. In Chess960 it is written
+     * as the king moving onto its own rook's square, like `e1h1`, and
+     * standard chess also takes the square the king ends up on, `e1g1`.
+     */
+    fn fits(&self, position: &Position, mv: &Move) -> bool {
+        if let Some(castle) = self.castle {
+            return mv.kind == castle;
+        }
+
+        let from = match (self.from_file, self.from_rank) {
+            (Some(file), Some(rank)) => Some(Square::new(file, rank)),
+            _ => None,
+        };
+
+        let kind_fits = match self.kind {
+            Some(kind) => mv.piece.kind == kind,
+            None => from.is_some() || mv.piece.kind == PieceKind::Pawn,
+        };
+
+        if !kind_fits
+            || self.from_file.is_some_and(|file| file != mv.from.file())
+            || self.from_rank.is_some_and(|rank| rank != mv.from.rank())
+            || self.promotion.is_some() && self.promotion != mv.promotion
+        {
+            return false;
+        }
+
+        let to = self.to_rank.map(|rank| Square::new(self.to_file, rank));
+
+        if mv.is_castle() {
+            let to_king = to == Some(mv.to) && !position.is_chess960();
+
+            return from.is_some() && (to_king || to == position.castling_rook(mv));
+        }
+
+        mv.to.file() == self.to_file
+            && (self.to_rank.is_none() || self.to_rank == Some(mv.to.rank()))
+    }
+}
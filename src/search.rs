@@ -0,0 +1,479 @@
+//! A small alpha-beta search, enough to pick moves and to run test suites
+//! against.
+
+use std::time::{Duration, Instant};
+
+use crate::bitboard::Squares;
+use crate::book::{Book, Selection};
+use crate::moves::Move;
+use crate::piece::{Colour, Piece, PieceKind};
+use crate::position::Position;
+use crate::tablebase::{Tablebases, Wdl};
+
+/// The score of checkmating right away. Mates further off score one less
+/// for every ply it takes to get there.
+pub const MATE: i32 = 30_000;
+
+/* Scores this close to `MATE` are mates rather than material.
+ */
+const MATE_BOUND: i32 = MATE - 1000;
+
+/// The score of a position the tablebases say is won, less one for every
+/// ply from the root to it. It stays below the mate scores, as the tables
+/// don't say how far off the mate is.
+pub const TABLEBASE_WIN: i32 = MATE_BOUND - 1000;
+
+/* How deep a search without a depth limit can go.
+ */
+const MAX_DEPTH: u32 = 64;
+
+/// How long a search may go on.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Limit {
+    /// Searches this many plies deep, plus captures.
+    Depth(u32),
+    /// Searches deeper and deeper until the time is up, keeping the result
+    /// of the deepest search that finished.
+    Time(Duration),
+}
+
+/// What a search found.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SearchResult {
+    /// The best move found, or `None` if there are no legal moves.
+    pub best_move: Option<Move>,
+    /// The score of the position for the side to move, in centipawns or
+    /// as a mate score counting down from [`MATE`](constant.MATE.html).
+    pub score: i32,
+    /// The depth of the deepest search that finished.
+    pub depth: u32,
+    /// The number of positions looked at.
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// The number of moves, not plies, to the mate the score points at.
+    /// Negative when the side to move is getting mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score >= MATE_BOUND {
+            Some((MATE - self.score + 1) / 2)
+        } else if self.score <= -MATE_BOUND {
+            Some(-(MATE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+}
+
+/// The computer player: the search along with the opening book it plays
+/// from and the endgame tablebases it plays into.
+#[derive(Clone, Default, Debug)]
+pub struct Engine {
+    book: Option<(Book, Selection)>,
+    tablebases: Option<Tablebases>,
+}
+
+impl Engine {
+    /// An engine that only searches.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plays from `book` while the game is in it, picking between the book
+    /// moves by `selection`.
+    pub fn set_book(&mut self, book: Book, selection: Selection) {
+        self.book = Some((book, selection));
+    }
+
+    /// The opening book, if there is one.
+    pub fn book(&self) -> Option<&Book> {
+        self.book.as_ref().map(|(book, _)| book)
+    }
+
+    /// Looks positions with few enough pieces up in `tablebases`, both
+    /// at the root and in the search.
+    pub fn set_tablebases(&mut self, tablebases: Tablebases) {
+        self.tablebases = Some(tablebases);
+    }
+
+    /// The tablebases, if there are any.
+    pub fn tablebases(&self) -> Option<&Tablebases> {
+        self.tablebases.as_ref()
+    }
+
+    /// Finds a move for the side to move. A move from the book is played
+    /// without searching and comes back with a depth, score and node count
+    /// of 0. So does a move from the tablebases, with a score from
+    /// [`TABLEBASE_WIN`](constant.TABLEBASE_WIN.html) down for a win.
+    pub fn think(&self, position: &Position, limit: Limit) -> SearchResult {
+        let book_move = self
+            .book
+            .as_ref()
+            .and_then(|(book, selection)| book.choose(position, *selection));
+
+        if let Some(mv) = book_move {
+            return SearchResult {
+                best_move: Some(mv),
+                score: 0,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+
+        let tablebases = self.tablebases.as_ref();
+
+        match tablebases.and_then(|tablebases| tablebases.best_move(position)) {
+            Some((mv, dtz)) => SearchResult {
+                best_move: Some(mv),
+                score: tablebase_score(dtz, position.halfmove()),
+                depth: 0,
+                nodes: 0,
+            },
+            None => run(position, limit, tablebases),
+        }
+    }
+}
+
+/* Scores a move with distance to zero `dtz` from a position whose
+ * fifty-move counter is at `halfmove`. A win the fifty-move rule stops
+ * short of is a draw.
+ */
+fn tablebase_score(dtz: i32, halfmove: u16) -> i32 {
+    if dtz == 0 || dtz.abs() + halfmove as i32 > 100 {
+        0
+    } else {
+        dtz.signum() * (TABLEBASE_WIN - dtz.abs())
+    }
+}
+
+/// Searches `position` for the best move of the side to move.
+pub fn search(position: &Position, limit: Limit) -> SearchResult {
+    run(position, limit, None)
+}
+
+/* Searches `position`, looking up the positions `tablebases` has after
+ * captures and pawn moves.
+ */
+fn run(position: &Position, limit: Limit, tablebases: Option<&Tablebases>) -> SearchResult {
+    let (max_depth, deadline) = match limit {
+        Limit::Depth(depth) => (depth.clamp(1, MAX_DEPTH), None),
+        Limit::Time(time) => (MAX_DEPTH, Some(Instant::now() + time)),
+    };
+
+    let mut searcher = Searcher {
+        deadline,
+        nodes: 0,
+        stopped: false,
+        tablebases,
+    };
+
+    let mut position = position.clone();
+    let mut moves = position.legal_moves();
+    order(&mut moves);
+
+    let mut result = SearchResult {
+        best_move: moves.first().copied(),
+        score: searcher.negamax(&mut position, 0, -MATE, MATE, 0),
+        depth: 0,
+        nodes: 0,
+    };
+
+    for depth in 1..=max_depth {
+        let mut alpha = -MATE;
+        let mut best = None;
+
+        for mv in &moves {
+            let undo = position.make_move(mv);
+            let score = -searcher.negamax(&mut position, depth - 1, -MATE, -alpha, 1);
+            position.unmake_move(mv, undo);
+
+            if searcher.stopped {
+                break;
+            }
+
+            if best.is_none() || score > alpha {
+                alpha = score;
+                best = Some(*mv);
+            }
+        }
+
+        if searcher.stopped || best.is_none() {
+            break;
+        }
+
+        result.best_move = best;
+        result.score = alpha;
+        result.depth = depth;
+
+        // Looking at the best move first makes the next search faster
+        if let Some(i) = moves.iter().position(|mv| Some(*mv) == best) {
+            moves[..=i].rotate_right(1);
+        }
+
+        if alpha.abs() >= MATE_BOUND {
+            break;
+        }
+    }
+
+    result.nodes = searcher.nodes;
+    result
+}
+
+/// Scores `position` without searching, in centipawns for the side to
+/// move: the material on the board and how well the pieces are placed.
+pub fn evaluate(position: &Position) -> i32 {
+    let mut score = 0;
+
+    for colour in Colour::ALL.iter() {
+        let sign = if *colour == Colour::White { 1 } else { -1 };
+
+        for kind in PieceKind::ALL.iter() {
+            for sq in Squares(position.pieces(Piece::new(*colour, *kind))) {
+                // The tables are written from white's side, 8th rank first
+                let rank = match colour {
+                    Colour::White => 7 - sq.rank(),
+                    Colour::Black => sq.rank(),
+                };
+                let index = rank as usize * 8 + sq.file() as usize;
+
+                score += sign * (value(*kind) + PLACEMENT[kind.index()][index]);
+            }
+        }
+    }
+
+    match position.player() {
+        Colour::White => score,
+        Colour::Black => -score,
+    }
+}
+
+/* The search state shared by every node.
+ */
+struct Searcher<'a> {
+    deadline: Option<Instant>,
+    nodes: u64,
+    stopped: bool,
+    tablebases: Option<&'a Tablebases>,
+}
+
+impl Searcher<'_> {
+    /* Scores `position` for the side to move, searching `depth` plies
+     * deep, with `ply` the distance from the root.
+     */
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        ply: i32,
+    ) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let mut moves = position.legal_moves();
+
+        if moves.is_empty() {
+            return if position.in_check(position.player()) {
+                -(MATE - ply)
+            } else {
+                0
+            };
+        }
+
+        if position.halfmove() >= 100 || position.is_dead() {
+            return 0;
+        }
+
+        // Right after a capture or pawn move the tables know the result
+        if ply > 0 && position.halfmove() == 0 {
+            let wdl = self
+                .tablebases
+                .and_then(|tablebases| tablebases.probe_wdl(position));
+
+            if let Some(wdl) = wdl {
+                return match wdl {
+                    Wdl::Win => TABLEBASE_WIN - ply,
+                    Wdl::Loss => -(TABLEBASE_WIN - ply),
+                    Wdl::CursedWin => 1,
+                    Wdl::BlessedLoss => -1,
+                    Wdl::Draw => 0,
+                };
+            }
+        }
+
+        if depth == 0 {
+            return self.quiesce(position, alpha, beta);
+        }
+
+        order(&mut moves);
+
+        for mv in &moves {
+            let undo = position.make_move(mv);
+            let score = -self.negamax(position, depth - 1, -beta, -alpha, ply + 1);
+            position.unmake_move(mv, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /* Plays out the captures until the position is quiet, so that a
+     * search never stops halfway through an exchange.
+     */
+    fn quiesce(&mut self, position: &mut Position, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let stand_pat = evaluate(position);
+
+        if stand_pat >= beta {
+            return beta;
+        }
+
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = position.captures();
+        order(&mut captures);
+
+        for mv in &captures {
+            let undo = position.make_move(mv);
+            let score = -self.quiesce(position, -beta, -alpha);
+            position.unmake_move(mv, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return beta;
+            }
+
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    /* Counts a node and checks the clock every few thousand of them.
+     */
+    fn out_of_time(&mut self) -> bool {
+        self.nodes += 1;
+
+        if self.nodes % 2048 == 0 {
+            if let Some(deadline) = self.deadline {
+                self.stopped |= Instant::now() >= deadline;
+            }
+        }
+
+        self.stopped
+    }
+}
+
+/* Puts promotions and captures of valuable pieces by cheap ones first, as
+ * they're the likeliest to cut the search short.
+ */
+fn order(moves: &mut [Move]) {
+    moves.sort_by_key(|mv| {
+        let victim = mv.captured.map_or(0, |pc| value(pc.kind));
+        let promotion = mv.promotion.map_or(0, value);
+        let attacker = if mv.is_capture() {
+            value(mv.piece.kind) / 10
+        } else {
+            0
+        };
+
+        -(victim * 10 + promotion - attacker)
+    });
+}
+
+/* The material value of a piece in centipawns.
+ */
+fn value(kind: PieceKind) -> i32 {
+    match kind {
+        PieceKind::Pawn => 100,
+        PieceKind::Knight => 320,
+        PieceKind::Bishop => 330,
+        PieceKind::Rook => 500,
+        PieceKind::Queen => 900,
+        PieceKind::King => 0,
+    }
+}
+
+/* Bonuses for where each kind of piece stands, from Tomasz Michniewski's
+ * Simplified Evaluation Function, from white's side with a8 first.
+ */
+#[rustfmt::skip]
+const PLACEMENT: [[i32; 64]; 6] = [
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+         50,  50,  50,  50,  50,  50,  50,  50,
+         10,  10,  20,  30,  30,  20,  10,  10,
+          5,   5,  10,  25,  25,  10,   5,   5,
+          0,   0,   0,  20,  20,   0,   0,   0,
+          5,  -5, -10,   0,   0, -10,  -5,   5,
+          5,  10,  10, -20, -20,  10,  10,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+        -50, -40, -30, -30, -30, -30, -40, -50,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -40, -20,   0,   5,   5,   0, -20, -40,
+        -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    [
+        -20, -10, -10, -10, -10, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
+        -10,  10,  10,  10,  10,  10,  10, -10,
+        -10,   5,   0,   0,   0,   0,   5, -10,
+        -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    [
+          0,   0,   0,   0,   0,   0,   0,   0,
+          5,  10,  10,  10,  10,  10,  10,   5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+          0,   0,   0,   5,   5,   0,   0,   0,
+    ],
+    [
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+          0,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   5,   5,   5,   5,   5,   0, -10,
+        -10,   0,   5,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    [
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -20, -30, -30, -40, -40, -30, -30, -20,
+        -10, -20, -20, -20, -20, -20, -20, -10,
+         20,  20,   0,   0,   0,   0,  20,  20,
+         20,  30,  10,   0,   0,  10,  30,  20,
+    ],
+];
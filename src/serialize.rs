@@ -0,0 +1,149 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! Types are written the way chess players write them rather than field by
+//! field: a position is its FEN, a square its name like `e4`, a piece its
+//! FEN letter, and a game its starting FEN with the moves in SAN. A game in
+//! JSON looks like
+//!
+//! ```json
+//! {
+//!   "start": "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+//!   "moves": ["f3", "e5", "g4", "Qh4#"],
+//!   "tags": [["Event", "?"], ["White", "Anderssen"]],
+//!   "draw_rule": "Automatic",
+//!   "outcome": "BlackWins"
+//! }
+//! ```
+//!
+//! Reading a game back plays its moves again, so they have to be legal.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::game::{DrawRule, Game, Outcome};
+use crate::piece::{Piece, PieceKind};
+use crate::position::Position;
+use crate::square::Square;
+
+/* Serializes a type as the text `to_text` gives and reads it back with
+ * `parse`, which is described as `what` in the error when it fails.
+ */
+macro_rules! as_text {
+    ($ty:ty, $what:expr, $to_text:expr, $parse:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(&$to_text(self))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserializer.deserialize_str(TextVisitor($what, $parse))
+            }
+        }
+    };
+}
+
+as_text! {
+    Position, "a FEN",
+    Position::to_fen,
+    |s: &str| Position::from_fen(s).ok()
+}
+
+as_text! {
+    Square, "a square like e4",
+    Square::to_string,
+    |s: &str| s.parse().ok()
+}
+
+as_text! {
+    Piece, "a FEN piece letter",
+    |pc: &Piece| pc.to_char(),
+    |s: &str| single_char(s).and_then(Piece::from_char)
+}
+
+as_text! {
+    PieceKind, "an upper case piece letter",
+    |kind: &PieceKind| kind.to_char(),
+    |s: &str| single_char(s).filter(char::is_ascii_uppercase).and_then(PieceKind::from_char)
+}
+
+/* Reads a string with the function it holds, failing with the description
+ * it holds.
+ */
+struct TextVisitor<F>(&'static str, F);
+
+impl<'de, T, F: FnOnce(&str) -> Option<T>> Visitor<'de> for TextVisitor<F> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+        let what = self.0;
+        (self.1)(s).ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &what))
+    }
+}
+
+/* The only character of `s`, if it has just the one.
+ */
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    let ch = chars.next()?;
+    chars.next().map_or(Some(ch), |_| None)
+}
+
+/* What's kept of a game: the rest is worked out again by replaying the
+ * moves.
+ */
+#[derive(Serialize, Deserialize)]
+struct GameRecord {
+    start: Position,
+    moves: Vec<String>,
+    tags: Vec<(String, String)>,
+    draw_rule: DrawRule,
+    outcome: Option<Outcome>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRecord {
+            start: self.start().clone(),
+            moves: self.san_moves(),
+            tags: self.tags().to_vec(),
+            draw_rule: self.draw_rule(),
+            outcome: self.outcome(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Replays the moves from the starting position. An outcome the moves
+/// don't lead to on their own, like a claimed draw or a loss on time, is
+/// taken from the record.
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = GameRecord::deserialize(deserializer)?;
+        let mut game = Game::from_position(record.start, record.draw_rule);
+
+        for san in &record.moves {
+            let mv = game
+                .position()
+                .parse_san(san)
+                .map_err(|e| de::Error::custom(format!("{}: {}", san, e)))?;
+            game.play(&mv)
+                .map_err(|e| de::Error::custom(format!("{}: {}", san, e)))?;
+        }
+
+        game.set_tags(record.tags);
+
+        if game.outcome().is_none() {
+            game.set_outcome(record.outcome);
+        }
+
+        Ok(game)
+    }
+}
@@ -0,0 +1,82 @@
+//! The squares of the board and their algebraic names.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A square of the board. Squares are numbered like the bits of a
+/// [`Bitboard`](../bitboard/type.Bitboard.html): a1 is 0, h1 is 7 and h8
+/// is 63. Files and ranks count from 0 as well, so e4 is file 4, rank 3.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Square(u8);
+
+impl Square {
+    /// The square on `file` and `rank`, which both have to be below 8.
+    pub const fn new(file: u8, rank: u8) -> Self {
+        Self(rank * 8 + file)
+    }
+
+    /// The square with the given index, which has to be below 64.
+    pub const fn from_index(index: usize) -> Self {
+        Self(index as u8)
+    }
+
+    /// The index of the square, from 0 for a1 up to 63 for h8.
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The file of the square, 0 for the a-file.
+    pub const fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    /// The rank of the square, 0 for the first rank.
+    pub const fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    /// The square `df` files and `dr` ranks away, or `None` if that's off
+    /// the board.
+    pub fn offset(self, df: i8, dr: i8) -> Option<Self> {
+        let file = self.file() as i8 + df;
+        let rank = self.rank() as i8 + dr;
+
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Self::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+}
+
+/// Prints the algebraic name of the square, like `e4`.
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+/// Returned when a string isn't the name of a square.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ParseSquareError;
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid square name")
+    }
+}
+
+impl Error for ParseSquareError {}
+
+/// Reads an algebraic square name like `e4`.
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Ok(Self::new(file - b'a', rank - b'1')),
+            _ => Err(ParseSquareError),
+        }
+    }
+}
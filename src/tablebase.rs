@@ -0,0 +1,1522 @@
+//! Syzygy endgame tablebases kept in a local directory.
+//!
+//! The win/draw/loss tables (`.rtbw`) say how a position ends with best
+//! play, and the distance-to-zero tables (`.rtbz`) how many plies it is to
+//! the next capture or pawn move on the way there. A table file is mapped
+//! into memory the first time a position needs it, so only the blocks that
+//! get probed are ever read from disk.
+//!
+//! The tables don't hold the right value for positions where a capture is
+//! the best move, so probing plays out the captures first and only looks
+//! up what's left.
+//!
+//! Reading and probing the tables follows Fathom
+//! (<https://github.com/jdart1/Fathom>), the MIT-licensed probing code by
+//! Ronald de Man, basil00 and Jon Dart. Where a function or table here does
+//! the job of one there, its comment gives the Fathom name.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::ops::Neg;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use memmap2::Mmap;
+
+use crate::bitboard::Squares;
+use crate::moves::{Move, MoveKind};
+use crate::piece::{Colour, Piece, PieceKind};
+use crate::position::Position;
+
+/* The first four bytes of every win/draw/loss and distance-to-zero table.
+ */
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+/* The order pieces are listed in table names.
+ */
+const NAME_ORDER: [PieceKind; 6] = [
+    PieceKind::King,
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+    PieceKind::Pawn,
+];
+
+/* Set in the byte after the magic number of win/draw/loss tables that hold
+ * both sides to move, and of tables with pawns.
+ */
+const SPLIT: u8 = 1;
+const HAS_PAWNS: u8 = 2;
+
+/* The flags of the values for one file of the lead pawn in a
+ * distance-to-zero table: which side to move they're for, and whether they
+ * go through a map and are counted in plies or moves.
+ */
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+/* Set when every value is the same, in any table.
+ */
+const SINGLE_VALUE: u8 = 128;
+
+/* By the win/draw/loss value from -2 to 2: Fathom's `WdlToMap`, the map a
+ * distance-to-zero value goes through, `PAFlags`, the flag saying it's in
+ * plies, and `WdlToDtz`, the distance to zero when the best move zeroes.
+ */
+const WDL_TO_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+const PA_FLAGS: [u8; 5] = [LOSS_PLIES, 0, 0, 0, WIN_PLIES];
+const WDL_TO_DTZ: [i32; 5] = [-1, -101, 0, 101, 1];
+
+/* Fathom's `Binomial`: the number of ways to pick k of n squares, by k and
+ * n.
+ */
+const BINOMIAL: [[u64; 64]; 7] = binomial();
+
+/* Fathom's `invTriangle` and `triangle`: the squares of the a1-d1-d4
+ * triangle, those off the diagonal first, and the number of each.
+ */
+const TRIANGLE_SQUARES: [usize; 10] = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+
+#[rustfmt::skip]
+const TRIANGLE: [usize; 64] = [
+    6, 0, 1, 2, 2, 1, 0, 6,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    2, 4, 5, 9, 9, 5, 4, 2,
+    1, 3, 8, 5, 5, 8, 3, 1,
+    0, 7, 3, 4, 4, 3, 7, 0,
+    6, 0, 1, 2, 2, 1, 0, 6,
+];
+
+/* Fathom's `lower`: numbers the squares below the a1-h8 diagonal.
+ */
+#[rustfmt::skip]
+const LOWER: [u64; 64] = [
+    28,  0,  1,  2,  3,  4,  5,  6,
+     0, 29,  7,  8,  9, 10, 11, 12,
+     1,  7, 30, 13, 14, 15, 16, 17,
+     2,  8, 13, 31, 18, 19, 20, 21,
+     3,  9, 14, 18, 32, 22, 23, 24,
+     4, 10, 15, 19, 22, 33, 25, 26,
+     5, 11, 16, 20, 23, 25, 34, 27,
+     6, 12, 17, 21, 24, 26, 27, 35,
+];
+
+/* Fathom's `flap`: numbers the squares the lead pawn can stand on, file by
+ * file from the a-file, with the e to h files mirrored.
+ */
+#[rustfmt::skip]
+const FLAP: [usize; 64] = [
+    0,  0,  0,  0,  0,  0,  0,  0,
+    0,  6, 12, 18, 18, 12,  6,  0,
+    1,  7, 13, 19, 19, 13,  7,  1,
+    2,  8, 14, 20, 20, 14,  8,  2,
+    3,  9, 15, 21, 21, 15,  9,  3,
+    4, 10, 16, 22, 22, 16, 10,  4,
+    5, 11, 17, 23, 23, 17, 11,  5,
+    0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+/* Fathom's `pawnTwist`: numbers the squares a pawn can stand on so that
+ * of two pawns the one nearer the edge, and then nearer the 2nd rank, has
+ * the higher number.
+ */
+#[rustfmt::skip]
+const PAWN_TWIST: [usize; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    47, 35, 23, 11, 10, 22, 34, 46,
+    45, 33, 21,  9,  8, 20, 32, 44,
+    43, 31, 19,  7,  6, 18, 30, 42,
+    41, 29, 17,  5,  4, 16, 28, 40,
+    39, 27, 15,  3,  2, 14, 26, 38,
+    37, 25, 13,  1,  0, 12, 24, 36,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+/* Fathom's `FileToFile`: the files folded onto a to d.
+ */
+const FILE_TO_FILE: [usize; 8] = [0, 1, 2, 3, 3, 2, 1, 0];
+
+/* Fathom's `KK_idx`, `PawnIdx` and `PawnFactorFile`.
+ */
+const KK_INDEX: [[u64; 64]; 10] = king_pairs();
+const PAWN_INDEX: [[u64; 24]; 6] = pawn_indices().0;
+const PAWN_FACTOR: [[u64; 4]; 6] = pawn_indices().1;
+
+const fn binomial() -> [[u64; 64]; 7] {
+    let mut table = [[0; 64]; 7];
+    let mut n = 0;
+
+    while n < 64 {
+        table[0][n] = 1;
+
+        let mut k = 1;
+        while k < 7 && k <= n {
+            table[k][n] = table[k - 1][n - 1] + table[k][n - 1];
+            k += 1;
+        }
+
+        n += 1;
+    }
+
+    table
+}
+
+/* Fathom's `off_diag`: how far `sq` is above the a1-h8 diagonal, negative
+ * below it.
+ */
+const fn off_diagonal(sq: usize) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+/* Numbers the 462 ways to place two kings that don't touch, with the first
+ * one in the triangle and the second not above the diagonal when the first
+ * is on it. Pairs with both kings on the diagonal come last.
+ */
+const fn king_pairs() -> [[u64; 64]; 10] {
+    let mut table = [[0; 64]; 10];
+    let mut code = 0;
+    let mut both_on_diagonal = 0;
+
+    while both_on_diagonal < 2 {
+        let mut i = 0;
+
+        while i < 10 {
+            let first = TRIANGLE_SQUARES[i];
+            let mut second = 0;
+
+            while second < 64 {
+                let touching =
+                    (first / 8).abs_diff(second / 8) <= 1 && (first % 8).abs_diff(second % 8) <= 1;
+                let above = off_diagonal(first) == 0 && off_diagonal(second) > 0;
+                let on_diagonal = off_diagonal(first) == 0 && off_diagonal(second) == 0;
+
+                if !touching && !above && on_diagonal == (both_on_diagonal == 1) {
+                    table[i][second] = code;
+                    code += 1;
+                }
+                second += 1;
+            }
+            i += 1;
+        }
+        both_on_diagonal += 1;
+    }
+
+    table
+}
+
+/* Fathom's `init_indices`: where the positions with the lead pawn on each
+ * square start, by the number of lead pawns and `FLAP`, and how many there
+ * are for each file.
+ */
+const fn pawn_indices() -> ([[u64; 24]; 6], [[u64; 4]; 6]) {
+    let mut index = [[0; 24]; 6];
+    let mut factor = [[0; 4]; 6];
+    let mut i = 0;
+
+    while i < 6 {
+        let mut s = 0;
+        let mut j = 0;
+
+        while j < 24 {
+            index[i][j] = s;
+            s += BINOMIAL[i][PAWN_TWIST[(j % 6 + 1) * 8 + j / 6]];
+
+            if (j + 1) % 6 == 0 {
+                factor[i][j / 6] = s;
+                s = 0;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+
+    (index, factor)
+}
+
+/// How a position ends with best play, for the side to move. A cursed win
+/// is a win the fifty-move rule turns into a draw, and a blessed loss a
+/// loss it saves.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    /* Reads a value from -2 for a loss to 2 for a win.
+     */
+    fn from_value(value: i32) -> Option<Self> {
+        match value {
+            -2 => Some(Self::Loss),
+            -1 => Some(Self::BlessedLoss),
+            0 => Some(Self::Draw),
+            1 => Some(Self::CursedWin),
+            2 => Some(Self::Win),
+            _ => None,
+        }
+    }
+}
+
+/// The result for the other side.
+impl Neg for Wdl {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Self::Loss => Self::Win,
+            Self::BlessedLoss => Self::CursedWin,
+            Self::Draw => Self::Draw,
+            Self::CursedWin => Self::BlessedLoss,
+            Self::Win => Self::Loss,
+        }
+    }
+}
+
+/// The tables found in a directory, by name, like `KRPvKR`.
+#[derive(Clone, Default, Debug)]
+pub struct Tablebases {
+    wdl: HashMap<String, Arc<Table>>,
+    dtz: HashMap<String, Arc<Table>>,
+    max_pieces: usize,
+}
+
+impl Tablebases {
+    /// Looks for `.rtbw` and `.rtbz` files in `dir`. Other files are
+    /// skipped, but a table file that doesn't start like one is an error.
+    /// The tables themselves are only read when they're first probed.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut tablebases = Self::default();
+
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            let (wdl, magic) = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("rtbw") => (true, WDL_MAGIC),
+                Some("rtbz") => (false, DTZ_MAGIC),
+                _ => continue,
+            };
+
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) if is_table_name(name) => name.to_string(),
+                _ => continue,
+            };
+
+            let mut start = [0; 4];
+            File::open(&path)?.read_exact(&mut start)?;
+
+            if start != magic {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} isn't a Syzygy table", path.display()),
+                ));
+            }
+
+            let material = Material::from_name(&name);
+            tablebases.max_pieces = tablebases.max_pieces.max(material.pieces);
+
+            let table = Arc::new(Table {
+                path,
+                wdl,
+                material,
+                data: OnceLock::new(),
+            });
+
+            if wdl {
+                tablebases.wdl.insert(name, table);
+            } else {
+                tablebases.dtz.insert(name, table);
+            }
+        }
+
+        Ok(tablebases)
+    }
+
+    /// The number of tables found, counting win/draw/loss and
+    /// distance-to-zero tables separately.
+    pub fn len(&self) -> usize {
+        self.wdl.len() + self.dtz.len()
+    }
+
+    /// Whether no tables were found at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The most pieces, kings included, any of the tables is for.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// The win/draw/loss table covering `position`, if there is one.
+    pub fn wdl_table(&self, position: &Position) -> Option<String> {
+        find_table(&self.wdl, position).map(|(name, _, _)| name.to_string())
+    }
+
+    /// The distance-to-zero table covering `position`, if there is one.
+    pub fn dtz_table(&self, position: &Position) -> Option<String> {
+        find_table(&self.dtz, position).map(|(name, _, _)| name.to_string())
+    }
+
+    /// How `position` ends with best play, leaving out the fifty-move
+    /// counter of the position itself. `None` if the tables needed aren't
+    /// there or can't be read.
+    pub fn probe_wdl(&self, position: &Position) -> Option<Wdl> {
+        if !self.covers(position) {
+            return None;
+        }
+
+        let (value, _) = self.wdl(&mut position.clone())?;
+        Wdl::from_value(value)
+    }
+
+    /// The number of plies to the next capture or pawn move with best
+    /// play: positive when the side to move wins, negative when it loses
+    /// and 0 for a draw. Cursed wins and blessed losses are 100 plies
+    /// further away, so that anything past 100 is a draw under the
+    /// fifty-move rule. Some tables count in moves, which can make the
+    /// distance one ply longer than it is.
+    pub fn probe_dtz(&self, position: &Position) -> Option<i32> {
+        if !self.covers(position) {
+            return None;
+        }
+
+        self.dtz(&mut position.clone(), false)
+    }
+
+    /// Every legal move of `position` with the distance to zero it leaves,
+    /// counted from `position`, as [`probe_dtz`](#method.probe_dtz) gives
+    /// it. A move that mates has a distance of 1.
+    pub fn root_moves(&self, position: &Position) -> Option<Vec<(Move, i32)>> {
+        if !self.covers(position) {
+            return None;
+        }
+
+        let mut position = position.clone();
+
+        position
+            .legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let undo = position.make_move(&mv);
+
+                let dtz = if position.halfmove() == 0 {
+                    self.wdl(&mut position)
+                        .map(|(value, _)| WDL_TO_DTZ[(2 - value) as usize])
+                } else {
+                    self.dtz(&mut position, false)
+                        .map(|dtz| -dtz - dtz.signum())
+                };
+                let mates = position.in_check(position.player()) && !position.has_legal_moves();
+
+                position.unmake_move(&mv, undo);
+
+                match dtz? {
+                    2 if mates => Some((mv, 1)),
+                    dtz => Some((mv, dtz)),
+                }
+            })
+            .collect()
+    }
+
+    /// The move that wins the quickest, or failing that draws, or failing
+    /// that loses the slowest, with its distance to zero.
+    pub fn best_move(&self, position: &Position) -> Option<(Move, i32)> {
+        self.root_moves(position)?
+            .into_iter()
+            .max_by_key(|(_, dtz)| match dtz.signum() {
+                1 => (2, -dtz),
+                0 => (1, 0),
+                _ => (0, -dtz),
+            })
+    }
+
+    /* Whether there can be a table for `position` at all.
+     */
+    fn covers(&self, position: &Position) -> bool {
+        position.castling() == 0 && position.occupied().count_ones() as usize <= self.max_pieces
+    }
+
+    /* Fathom's `probe_wdl`: the value of `position` from -2 to 2, and
+     * whether the best move is a capture, whose value the table doesn't
+     * have.
+     */
+    fn wdl(&self, position: &mut Position) -> Option<(i32, bool)> {
+        // -3 is less than any value, for no capture
+        let mut best_capture = -3;
+        let mut best_en_passant = -3;
+
+        for mv in position.captures() {
+            let undo = position.make_move(&mv);
+            let value = self.probe_ab(position, -2, -best_capture);
+            position.unmake_move(&mv, undo);
+
+            let value = -value?;
+
+            if value > best_capture {
+                if value == 2 {
+                    return Some((2, true));
+                }
+
+                if mv.kind != MoveKind::EnPassant {
+                    best_capture = value;
+                } else if value > best_en_passant {
+                    best_en_passant = value;
+                }
+            }
+        }
+
+        let value = self.probe_wdl_table(position)?;
+
+        // The table leaves en passant out, so the capture counts when it's
+        // better than the value stored
+        if best_en_passant > best_capture {
+            if best_en_passant > value {
+                return Some((best_en_passant, true));
+            }
+            best_capture = best_en_passant;
+        }
+
+        if best_capture >= value {
+            return Some((best_capture, best_capture > 0));
+        }
+
+        // and also when it's the only legal move, as the table has the
+        // position down as stalemate
+        if best_en_passant > -3 && value == 0 {
+            let only_en_passant = position
+                .legal_moves()
+                .iter()
+                .all(|mv| mv.kind == MoveKind::EnPassant);
+
+            if only_en_passant {
+                return Some((best_en_passant, true));
+            }
+        }
+
+        Some((value, false))
+    }
+
+    /* Fathom's `probe_ab`: the value of `position` with its captures played
+     * out, as far as it's between `alpha` and `beta`.
+     */
+    fn probe_ab(&self, position: &mut Position, mut alpha: i32, beta: i32) -> Option<i32> {
+        for mv in position.captures() {
+            let undo = position.make_move(&mv);
+            let value = self.probe_ab(position, -beta, -alpha);
+            position.unmake_move(&mv, undo);
+
+            let value = -value?;
+
+            if value > alpha {
+                if value >= beta {
+                    return Some(value);
+                }
+                alpha = value;
+            }
+        }
+
+        Some(alpha.max(self.probe_wdl_table(position)?))
+    }
+
+    /* Fathom's `probe_dtz`. Tables for one side to move only are searched
+     * a ply deep for the other side, `other_side` being set for that ply.
+     */
+    fn dtz(&self, position: &mut Position, other_side: bool) -> Option<i32> {
+        let (wdl, capture) = self.wdl(position)?;
+
+        if wdl == 0 {
+            return Some(0);
+        }
+
+        if capture {
+            return Some(WDL_TO_DTZ[(wdl + 2) as usize]);
+        }
+
+        let moves = position.legal_moves();
+        let pawn_move = |mv: &&Move| mv.piece.kind == PieceKind::Pawn && !mv.is_capture();
+
+        // A pawn move that keeps the win is as quick as it gets
+        if wdl > 0 {
+            for mv in moves.iter().filter(pawn_move) {
+                let undo = position.make_move(mv);
+                let value = self.wdl(position);
+                position.unmake_move(mv, undo);
+
+                if -value?.0 == wdl {
+                    return Some(WDL_TO_DTZ[(wdl + 2) as usize]);
+                }
+            }
+        }
+
+        if let Some(dtz) = self.probe_dtz_table(position, wdl)? {
+            return Some(WDL_TO_DTZ[(wdl + 2) as usize] + if wdl > 0 { dtz } else { -dtz });
+        }
+
+        if other_side {
+            return None;
+        }
+
+        let mut best = if wdl > 0 {
+            None
+        } else {
+            Some(WDL_TO_DTZ[(wdl + 2) as usize])
+        };
+
+        for mv in moves.iter().filter(|mv| !mv.is_capture() && !pawn_move(mv)) {
+            let undo = position.make_move(mv);
+            let value = self.dtz(position, true);
+            let mates = position.in_check(position.player()) && !position.has_legal_moves();
+            position.unmake_move(mv, undo);
+
+            let value = -value?;
+
+            if value == 1 && mates {
+                best = Some(1);
+            } else if wdl > 0 {
+                if value > 0 && best.map_or(true, |best| value + 1 < best) {
+                    best = Some(value + 1);
+                }
+            } else if best.map_or(true, |best| value - 1 < best) {
+                best = Some(value - 1);
+            }
+        }
+
+        best
+    }
+
+    /* The win/draw/loss table value of `position`, which is only right
+     * when no capture is the best move.
+     */
+    fn probe_wdl_table(&self, position: &Position) -> Option<i32> {
+        if position.occupied().count_ones() == 2 {
+            return Some(0);
+        }
+
+        let (_, table, flipped) = find_table(&self.wdl, position)?;
+
+        match table.probe(position, flipped)? {
+            Stored::Value(value, _) if value <= 4 => Some(value as i32 - 2),
+            _ => None,
+        }
+    }
+
+    /* The distance-to-zero table value of `position`, whose win/draw/loss
+     * value is `wdl`, in plies. `Some(None)` if the table is for the other
+     * side to move.
+     */
+    fn probe_dtz_table(&self, position: &Position, wdl: i32) -> Option<Option<i32>> {
+        let (_, table, flipped) = find_table(&self.dtz, position)?;
+
+        match table.probe(position, flipped)? {
+            Stored::Value(value, file) => table.data()?.dtz(file, value, wdl).map(Some),
+            Stored::OtherSide => Some(None),
+        }
+    }
+}
+
+/// The name of the table for the material of `position`, white's pieces
+/// first, like `KRPvKR`. Tables only exist for one way round, so the table
+/// for a position can also be named with black's pieces first.
+pub fn table_name(position: &Position) -> String {
+    let side = |colour: Colour| -> String {
+        NAME_ORDER
+            .iter()
+            .flat_map(|kind| {
+                let count = position.pieces(Piece::new(colour, *kind)).count_ones();
+                std::iter::repeat(kind.to_char()).take(count as usize)
+            })
+            .collect()
+    };
+
+    format!("{}v{}", side(Colour::White), side(Colour::Black))
+}
+
+/* The table in `tables` for the material of `position`, either way round,
+ * and whether it has black's pieces first. Tables don't know about
+ * castling, so positions with castling rights never have one.
+ */
+fn find_table<'a>(
+    tables: &'a HashMap<String, Arc<Table>>,
+    position: &Position,
+) -> Option<(&'a str, &'a Table, bool)> {
+    if position.castling() != 0 {
+        return None;
+    }
+
+    let name = table_name(position);
+    let (white, black) = name.split_once('v')?;
+    let flipped = format!("{}v{}", black, white);
+
+    if let Some((name, table)) = tables.get_key_value(&name) {
+        return Some((name, table, false));
+    }
+
+    tables
+        .get_key_value(&flipped)
+        .map(|(name, table)| (name.as_str(), &**table, true))
+}
+
+/* Whether `name` is written like a table name: two sides of pieces with a
+ * king each, split by a `v`.
+ */
+fn is_table_name(name: &str) -> bool {
+    match name.split_once('v') {
+        Some((white, black)) => [white, black]
+            .iter()
+            .all(|side| side.starts_with('K') && side[1..].chars().all(|ch| "QRBNP".contains(ch))),
+        None => false,
+    }
+}
+
+/* What the name of a table says about its pieces, white being the side
+ * before the `v`.
+ */
+#[derive(Clone, Debug)]
+struct Material {
+    pieces: usize,
+    has_pawns: bool,
+    /* Fathom's `kk_enc`: whether no piece other than a king is the only
+     * one of its kind on its side, so that the two kings lead.
+     */
+    kings_lead: bool,
+    symmetric: bool,
+    /* The pawns of the side the lead pawns belong to, then the other
+     * side's. The lead side is the one with fewer pawns, but some.
+     */
+    pawns: [usize; 2],
+}
+
+impl Material {
+    fn from_name(name: &str) -> Self {
+        let (white, black) = name.split_once('v').unwrap_or((name, ""));
+        let count = |side: &str, piece| side.chars().filter(|ch| *ch == piece).count();
+
+        let unique = [white, black]
+            .iter()
+            .any(|side| "QRBNP".chars().any(|piece| count(side, piece) == 1));
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+
+        Self {
+            pieces: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            kings_lead: !unique,
+            symmetric: white == black,
+            pawns: if white_leads {
+                [white_pawns, black_pawns]
+            } else {
+                [black_pawns, white_pawns]
+            },
+        }
+    }
+}
+
+/* What a table holds for a position: the value, with the file of the lead
+ * pawn it was found under, or nothing as the table is for the other side
+ * to move.
+ */
+enum Stored {
+    Value(u16, usize),
+    OtherSide,
+}
+
+/* One table file.
+ */
+struct Table {
+    path: PathBuf,
+    wdl: bool,
+    material: Material,
+    data: OnceLock<Option<TableData>>,
+}
+
+impl Table {
+    /* The file mapped into memory, with its layout read the first time
+     * it's asked for. `None` if the file can't be read or doesn't make
+     * sense.
+     */
+    fn data(&self) -> Option<&TableData> {
+        self.data
+            .get_or_init(|| TableData::map(&self.path, &self.material, self.wdl))
+            .as_ref()
+    }
+
+    /* Fathom's `probe_table`: finds the value of `position`, which has
+     * black's pieces first in the table name if `flipped`. Tables are
+     * written with white as the side named first, so that side is turned
+     * into white and the board flipped with it.
+     */
+    fn probe(&self, position: &Position, flipped: bool) -> Option<Stored> {
+        let data = self.data()?;
+        let material = &self.material;
+
+        // Symmetric tables hold white to move only
+        let (flip, side) = if material.symmetric {
+            (position.player() == Colour::Black, 0)
+        } else {
+            let side = (position.player() == Colour::White) == flipped;
+            (flipped, side as usize)
+        };
+
+        let mut squares = vec![0; material.pieces];
+
+        if !material.has_pawns {
+            let other_side = !self.wdl && (data.dtz_flags[0] & STM) as usize != side;
+
+            if other_side && !material.symmetric {
+                return Some(Stored::OtherSide);
+            }
+
+            let ei = data.enc_info(0, side)?;
+            let mut i = 0;
+
+            while i < material.pieces {
+                i = fill_squares(position, &ei.pieces, flip, 0, &mut squares, i)?;
+            }
+
+            let idx = ei.encode_piece(&mut squares, material)?;
+            let value = ei.precomp.decompress(&data.bytes, idx)?;
+
+            return Some(Stored::Value(value, 0));
+        }
+
+        let mirror = if flip { 0x38 } else { 0 };
+        let lead = &data.enc_info(0, 0)?.pieces;
+        let mut i = fill_squares(position, lead, flip, mirror, &mut squares, 0)?;
+        let file = leading_pawn(&mut squares, material.pawns[0]);
+
+        if !self.wdl && (*data.dtz_flags.get(file)? & STM) as usize != side {
+            return Some(Stored::OtherSide);
+        }
+
+        let ei = data.enc_info(file, side)?;
+
+        while i < material.pieces {
+            i = fill_squares(position, &ei.pieces, flip, mirror, &mut squares, i)?;
+        }
+
+        let idx = ei.encode_pawn(&mut squares, material)?;
+        let value = ei.precomp.decompress(&data.bytes, idx)?;
+
+        Some(Stored::Value(value, file))
+    }
+}
+
+/// Shows the path, as the contents can take up a lot of room.
+impl fmt::Debug for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Table").field("path", &self.path).finish()
+    }
+}
+
+/* Reads a piece the way tables number them, 1 to 6 from pawn to king with
+ * 8 added for black.
+ */
+fn piece_from_code(code: u8) -> Option<Piece> {
+    let kind = *PieceKind::ALL.get((code & 7).checked_sub(1)? as usize)?;
+    let colour = if code & 8 == 0 {
+        Colour::White
+    } else {
+        Colour::Black
+    };
+
+    Some(Piece::new(colour, kind))
+}
+
+/* Fathom's `fill_squares`: puts the squares of the pieces like
+ * `pieces[i]`, with the colours swapped if `flip`, into `squares` from `i`
+ * on, and gives the index after them.
+ */
+fn fill_squares(
+    position: &Position,
+    pieces: &[Piece],
+    flip: bool,
+    mirror: usize,
+    squares: &mut [usize],
+    mut i: usize,
+) -> Option<usize> {
+    let pc = *pieces.get(i)?;
+    let pc = if flip {
+        Piece::new(pc.colour.switch(), pc.kind)
+    } else {
+        pc
+    };
+    let start = i;
+
+    for sq in Squares(position.pieces(pc)) {
+        *squares.get_mut(i)? = sq.index() ^ mirror;
+        i += 1;
+    }
+
+    if i == start {
+        return None;
+    }
+
+    Some(i)
+}
+
+/* Fathom's `leading_pawn`: moves the lead pawn the table numbers first to
+ * the front of `squares`, and gives its file folded onto a to d.
+ */
+fn leading_pawn(squares: &mut [usize], count: usize) -> usize {
+    for i in 1..count {
+        if FLAP[squares[0]] > FLAP[squares[i]] {
+            squares.swap(0, i);
+        }
+    }
+
+    FILE_TO_FILE[squares[0] & 7]
+}
+
+/* A table file mapped into memory, with Fathom's `EncInfo` for each file of
+ * the lead pawn, or just one without pawns, and after them the same again
+ * for black to move in tables that hold both sides.
+ */
+struct TableData {
+    bytes: Mmap,
+    ei: Vec<EncInfo>,
+    files: usize,
+    split: bool,
+    /* For distance-to-zero tables, the flags of each file, and where its
+     * four maps from stored values to distances start.
+     */
+    dtz_flags: Vec<u8>,
+    dtz_maps: Vec<[usize; 4]>,
+}
+
+impl TableData {
+    /* Fathom's `init_table`: maps the file and reads where everything is.
+     */
+    fn map(path: &Path, material: &Material, wdl: bool) -> Option<Self> {
+        let file = File::open(path).ok()?;
+
+        // SAFETY: the map is only ever read, and table files aren't written
+        // once they're published. Should one be cut short while it's mapped
+        // anyway, reading past the new end faults like a failed disk read.
+        let bytes = unsafe { Mmap::map(&file) }.ok()?;
+
+        let magic = if wdl { WDL_MAGIC } else { DTZ_MAGIC };
+
+        if bytes.get(..4)? != magic || (bytes.get(4)? & HAS_PAWNS != 0) != material.has_pawns {
+            return None;
+        }
+
+        let split = wdl && bytes[4] & SPLIT != 0;
+        let files = if material.has_pawns { 4 } else { 1 };
+        let sides = if split { 2 } else { 1 };
+        let more_pawns = material.has_pawns && material.pawns[1] > 0;
+
+        let mut at = 5;
+        let mut ei = Vec::with_capacity(files * sides);
+        let mut tb_sizes = Vec::with_capacity(files * sides);
+
+        for side in 0..sides {
+            let mut at = at;
+
+            for file in 0..files {
+                let (info, size) = EncInfo::new(bytes.get(at..)?, 4 * side as u32, file, material)?;
+                ei.push(info);
+                tb_sizes.push(size);
+                at += material.pieces + 1 + more_pawns as usize;
+            }
+        }
+
+        at += files * (material.pieces + 1 + more_pawns as usize);
+        at += at & 1;
+
+        // The compressed values are laid out file by file, both sides to
+        // move together
+        let order: Vec<usize> = (0..files)
+            .flat_map(|file| (0..sides).map(move |side| file + side * files))
+            .collect();
+
+        let mut sizes = vec![[0; 3]; ei.len()];
+        let mut dtz_flags = Vec::new();
+
+        for &t in &order {
+            let (precomp, flags, size) = PairsData::new(&bytes, &mut at, tb_sizes[t], wdl)?;
+            ei[t].precomp = precomp;
+            sizes[t] = size;
+
+            if !wdl {
+                dtz_flags.push(flags);
+            }
+        }
+
+        let mut dtz_maps = Vec::new();
+
+        if !wdl {
+            for &flags in &dtz_flags {
+                let mut maps = [0; 4];
+
+                if flags & MAPPED != 0 {
+                    if flags & WIDE == 0 {
+                        for map in maps.iter_mut() {
+                            *map = at + 1;
+                            at += 1 + *bytes.get(at)? as usize;
+                        }
+                    } else {
+                        at += at & 1;
+
+                        for map in maps.iter_mut() {
+                            *map = at + 2;
+                            at += 2 + 2 * read_u16(&bytes, at)? as usize;
+                        }
+                    }
+                }
+
+                dtz_maps.push(maps);
+            }
+
+            at += at & 1;
+        }
+
+        for &t in &order {
+            ei[t].precomp.index_table = at;
+            at = at.checked_add(sizes[t][0])?;
+        }
+
+        for &t in &order {
+            ei[t].precomp.size_table = at;
+            at = at.checked_add(sizes[t][1])?;
+        }
+
+        for &t in &order {
+            at = at.checked_add(63)? & !63;
+            ei[t].precomp.data = at;
+            at = at.checked_add(sizes[t][2])?;
+        }
+
+        Some(Self {
+            bytes,
+            ei,
+            files,
+            split,
+            dtz_flags,
+            dtz_maps,
+        })
+    }
+
+    /* The encoding for the lead pawn on `file`, or the only one without
+     * pawns, and the side to move `side` holds for.
+     */
+    fn enc_info(&self, file: usize, side: usize) -> Option<&EncInfo> {
+        let side = if self.split { side } else { 0 };
+        self.ei.get(file + side * self.files)
+    }
+
+    /* Turns `value` from a distance-to-zero table, found under `file`, into
+     * plies for a position whose win/draw/loss value is `wdl`.
+     */
+    fn dtz(&self, file: usize, value: u16, wdl: i32) -> Option<i32> {
+        let flags = *self.dtz_flags.get(file)?;
+        let wdl = usize::try_from(wdl + 2).ok().filter(|wdl| *wdl < 5)?;
+        let mut value = value as usize;
+
+        if flags & MAPPED != 0 {
+            let map = self.dtz_maps.get(file)?[WDL_TO_MAP[wdl]];
+
+            value = if flags & WIDE == 0 {
+                *self.bytes.get(map.checked_add(value)?)? as usize
+            } else {
+                read_u16(&self.bytes, map.checked_add(2 * value)?)? as usize
+            };
+        }
+
+        // Cursed wins and blessed losses are always counted in moves
+        if flags & PA_FLAGS[wdl] == 0 || wdl % 2 == 1 {
+            value *= 2;
+        }
+
+        Some(value as i32)
+    }
+}
+
+/* Fathom's `EncInfo`: how the pieces of a table are numbered for one file
+ * of the lead pawn and one side to move. The pieces are put in groups, the
+ * leading pieces or pawns first and then pieces of the same kind, and each
+ * group adds its number times `factor` to the index.
+ */
+#[derive(Default)]
+struct EncInfo {
+    precomp: PairsData,
+    pieces: Vec<Piece>,
+    /* The size of the group starting at each piece, 0 inside a group.
+     */
+    norm: Vec<usize>,
+    factor: Vec<u64>,
+}
+
+impl EncInfo {
+    /* Fathom's `init_enc_info`: reads the pieces and the order of their
+     * groups from `tb`, in the nibbles at `shift`. Gives the number of
+     * positions along with them.
+     */
+    fn new(tb: &[u8], shift: u32, file: usize, material: &Material) -> Option<(Self, u64)> {
+        let n = material.pieces;
+        let more_pawns = material.has_pawns && material.pawns[1] > 0;
+
+        let pieces = (0..n)
+            .map(|i| piece_from_code((tb.get(i + 1 + more_pawns as usize)? >> shift) & 0xf))
+            .collect::<Option<Vec<_>>>()?;
+
+        let order = ((tb.first()? >> shift) & 0xf) as usize;
+        let order2 = if more_pawns {
+            ((tb.get(1)? >> shift) & 0xf) as usize
+        } else {
+            0xf
+        };
+
+        let mut norm = vec![0; n];
+        let mut factor = vec![0; n];
+
+        let mut k = if material.has_pawns {
+            material.pawns[0]
+        } else if material.kings_lead {
+            2
+        } else {
+            3
+        };
+        norm[0] = k;
+
+        if more_pawns {
+            norm[k] = material.pawns[1];
+            k += norm[k];
+        }
+
+        let mut i = k;
+        while i < n {
+            norm[i] = pieces[i..]
+                .iter()
+                .take_while(|pc| **pc == pieces[i])
+                .count();
+            i += norm[i];
+        }
+
+        let mut free = 64 - k;
+        let mut f: u64 = 1;
+        let mut i = 0;
+
+        while k < n || i == order || i == order2 {
+            let size = if i == order {
+                factor[0] = f;
+
+                if material.has_pawns {
+                    *PAWN_FACTOR.get(norm[0].checked_sub(1)?)?.get(file)?
+                } else if material.kings_lead {
+                    462
+                } else {
+                    31332
+                }
+            } else if i == order2 {
+                factor[norm[0]] = f;
+                *BINOMIAL.get(norm[norm[0]])?.get(48 - norm[0])?
+            } else {
+                factor[k] = f;
+                let size = *BINOMIAL.get(norm[k])?.get(free)?;
+                free -= norm[k];
+                k += norm[k];
+                size
+            };
+
+            f = f.checked_mul(size)?;
+            i += 1;
+        }
+
+        let info = Self {
+            precomp: PairsData::default(),
+            pieces,
+            norm,
+            factor,
+        };
+
+        Some((info, f))
+    }
+
+    /* Fathom's `encode_piece`: the index of the pieces on `p` in a table
+     * without pawns. The board is mirrored to bring the first piece into
+     * the a1-d1-d4 triangle, and the first of the leading pieces off the
+     * diagonal below it.
+     */
+    fn encode_piece(&self, p: &mut [usize], material: &Material) -> Option<u64> {
+        if p[0] & 0x04 != 0 {
+            p.iter_mut().for_each(|sq| *sq ^= 0x07);
+        }
+
+        if p[0] & 0x20 != 0 {
+            p.iter_mut().for_each(|sq| *sq ^= 0x38);
+        }
+
+        let lead = if material.kings_lead { 2 } else { 3 };
+
+        if let Some(i) = p.iter().position(|sq| off_diagonal(*sq) != 0) {
+            if i < lead && off_diagonal(p[i]) > 0 {
+                p.iter_mut()
+                    .for_each(|sq| *sq = ((*sq >> 3) | (*sq << 3)) & 63);
+            }
+        }
+
+        let idx = if material.kings_lead {
+            KK_INDEX[TRIANGLE[p[0]]][p[1]]
+        } else {
+            let (s0, s1, s2) = (p[0], p[1], p[2]);
+            let i = (s1 > s0) as u64;
+            let j = (s2 > s0) as u64 + (s2 > s1) as u64;
+            let diag = |sq: usize| (sq / 8) as u64;
+
+            if off_diagonal(s0) != 0 {
+                (TRIANGLE[s0] as u64 * 63 + s1 as u64 - i) * 62 + s2 as u64 - j
+            } else if off_diagonal(s1) != 0 {
+                6 * 63 * 62 + diag(s0) * 28 * 62 + LOWER[s1] * 62 + s2 as u64 - j
+            } else if off_diagonal(s2) != 0 {
+                6 * 63 * 62 + 4 * 28 * 62 + diag(s0) * 7 * 28 + (diag(s1) - i) * 28 + LOWER[s2]
+            } else {
+                6 * 63 * 62
+                    + 4 * 28 * 62
+                    + 4 * 7 * 28
+                    + diag(s0) * 7 * 6
+                    + (diag(s1) - i) * 6
+                    + diag(s2)
+                    - j
+            }
+        };
+
+        self.encode_groups(p, lead, idx.checked_mul(self.factor[0])?)
+    }
+
+    /* Fathom's `encode_pawn_f`: the index of the pieces on `p` in a table
+     * with pawns, the lead pawn being first and on the a to d files once
+     * the board is mirrored.
+     */
+    fn encode_pawn(&self, p: &mut [usize], material: &Material) -> Option<u64> {
+        if p[0] & 0x04 != 0 {
+            p.iter_mut().for_each(|sq| *sq ^= 0x07);
+        }
+
+        let k = material.pawns[0];
+        p[1..k].sort_unstable_by(|a, b| PAWN_TWIST[*b].cmp(&PAWN_TWIST[*a]));
+
+        let mut idx = *PAWN_INDEX.get(k - 1)?.get(FLAP[p[0]])?;
+
+        for i in 1..k {
+            idx += BINOMIAL.get(k - i)?[PAWN_TWIST[p[i]]];
+        }
+
+        idx = idx.checked_mul(self.factor[0])?;
+
+        // The other side's pawns can't be on the first or last rank either
+        if material.pawns[1] > 0 {
+            let t = k + material.pawns[1];
+            p[k..t].sort_unstable();
+
+            let mut s = 0;
+
+            for i in k..t {
+                let skips = p[..k].iter().filter(|sq| p[i] > **sq).count();
+                s += BINOMIAL[i - k + 1].get(p[i].checked_sub(skips + 8)?)?;
+            }
+
+            idx = idx.checked_add(s.checked_mul(self.factor[k])?)?;
+            return self.encode_groups(p, t, idx);
+        }
+
+        self.encode_groups(p, k, idx)
+    }
+
+    /* The rest of Fathom's `encode`: each group from `k` on is numbered by
+     * the squares the ones before it leave free.
+     */
+    fn encode_groups(&self, p: &mut [usize], mut k: usize, mut idx: u64) -> Option<u64> {
+        while k < p.len() {
+            let t = k + self.norm[k];
+
+            if t == k || t > p.len() {
+                return None;
+            }
+
+            p[k..t].sort_unstable();
+
+            let mut s = 0;
+
+            for i in k..t {
+                let skips = p[..k].iter().filter(|sq| p[i] > **sq).count();
+                s += BINOMIAL[i - k + 1].get(p[i] - skips)?;
+            }
+
+            idx = idx.checked_add(s.checked_mul(self.factor[k])?)?;
+            k = t;
+        }
+
+        Some(idx)
+    }
+}
+
+/* Fathom's `PairsData`: where the compressed values of one encoding are in
+ * the file.
+ *
+ * The values are compressed by replacing pairs of symbols with new symbols
+ * over and over, and then with canonical Huffman codes in blocks of the
+ * same size. Every so many values an entry of the index table says which
+ * block they're in, and the size table gives the number of values in each
+ * block.
+ */
+#[derive(Default)]
+struct PairsData {
+    /* Set when every value is the same.
+     */
+    const_value: Option<u16>,
+    index_table: usize,
+    size_table: usize,
+    data: usize,
+    /* Where the pairs each symbol stands for start, and where the first
+     * symbol of each code length is.
+     */
+    sym_pat: usize,
+    offset: usize,
+    block_size: u32,
+    idx_bits: u32,
+    min_len: u32,
+    real_num_blocks: usize,
+    /* By symbol, the number of values it stands for less one.
+     */
+    sym_len: Vec<u32>,
+    /* By code length from `min_len` up, the lowest code of that length
+     * shifted to the top of 64 bits.
+     */
+    base: Vec<u64>,
+}
+
+impl PairsData {
+    /* Fathom's `setup_pairs`: reads the header of the values at `at` in
+     * `bytes` for `tb_size` positions, moving `at` past it. Gives the flags
+     * and the sizes of the index table, size table and data too.
+     */
+    fn new(
+        bytes: &[u8],
+        at: &mut usize,
+        tb_size: u64,
+        wdl: bool,
+    ) -> Option<(Self, u8, [usize; 3])> {
+        let data = bytes.get(*at..)?;
+        let flags = *data.first()?;
+
+        if flags & SINGLE_VALUE != 0 {
+            let value = if wdl { *data.get(1)? as u16 } else { 0 };
+            let pairs = Self {
+                const_value: Some(value),
+                ..Self::default()
+            };
+
+            *at += 2;
+            return Some((pairs, flags, [0; 3]));
+        }
+
+        let header = data.get(..10)?;
+        let block_size = header[1] as u32;
+        let idx_bits = header[2] as u32;
+        let real_num_blocks = read_u32(header, 4)? as usize;
+        let num_blocks = real_num_blocks + header[3] as usize;
+        let max_len = header[8] as u32;
+        let min_len = header[9] as u32;
+
+        if min_len == 0 || max_len < min_len || max_len > 32 || idx_bits == 0 || idx_bits > 32 {
+            return None;
+        }
+
+        let h = (max_len - min_len + 1) as usize;
+        let num_syms = read_u16(data, 10 + 2 * h)? as usize;
+
+        let mut pairs = Self {
+            const_value: None,
+            sym_pat: *at + 12 + 2 * h,
+            offset: *at + 10,
+            block_size,
+            idx_bits,
+            min_len,
+            real_num_blocks,
+            ..Self::default()
+        };
+
+        *at += 12 + 2 * h + 3 * num_syms + (num_syms & 1);
+
+        let num_indices = (tb_size + (1 << idx_bits) - 1) >> idx_bits;
+        let sizes = [
+            usize::try_from(num_indices.checked_mul(6)?).ok()?,
+            num_blocks.checked_mul(2)?,
+            real_num_blocks.checked_shl(block_size)?,
+        ];
+
+        pairs.sym_len = vec![0; num_syms];
+        let mut done = vec![false; num_syms];
+
+        for sym in 0..num_syms {
+            if !done[sym] {
+                pairs.calc_sym_len(bytes, sym, &mut done)?;
+            }
+        }
+
+        pairs.base = vec![0; h];
+
+        for i in (0..h - 1).rev() {
+            let offset = read_u16(bytes, pairs.offset + 2 * i)? as u64;
+            let next = read_u16(bytes, pairs.offset + 2 * i + 2)? as u64;
+            pairs.base[i] = pairs.base[i + 1].wrapping_add(offset).wrapping_sub(next) / 2;
+        }
+
+        for (i, base) in pairs.base.iter_mut().enumerate() {
+            *base = base.checked_shl(64 - (min_len + i as u32)).unwrap_or(0);
+        }
+
+        Some((pairs, flags, sizes))
+    }
+
+    /* The two symbols `sym` stands for, or `None` for a symbol that stands
+     * for one value.
+     */
+    fn pair(&self, bytes: &[u8], sym: usize) -> Option<Option<(usize, usize)>> {
+        let w = bytes.get(self.sym_pat + 3 * sym..self.sym_pat + 3 * sym + 3)?;
+        let right = ((w[2] as usize) << 4) | (w[1] as usize >> 4);
+
+        if right == 0xfff {
+            return Some(None);
+        }
+
+        let left = ((w[1] as usize & 0xf) << 8) | w[0] as usize;
+
+        Some(Some((left, right)))
+    }
+
+    /* Fathom's `calc_symLen`. A symbol is marked done before the ones it
+     * stands for are looked at, so a file where a symbol stands for itself
+     * can't loop forever.
+     */
+    fn calc_sym_len(&mut self, bytes: &[u8], sym: usize, done: &mut [bool]) -> Option<()> {
+        done[sym] = true;
+
+        if let Some((left, right)) = self.pair(bytes, sym)? {
+            for half in [left, right] {
+                if !*done.get(half)? {
+                    self.calc_sym_len(bytes, half, done)?;
+                }
+            }
+
+            self.sym_len[sym] = self.sym_len[left] + self.sym_len[right] + 1;
+        }
+
+        Some(())
+    }
+
+    /* Fathom's `decompress_pairs`: the value at `idx`.
+     */
+    fn decompress(&self, bytes: &[u8], idx: u64) -> Option<u16> {
+        if let Some(value) = self.const_value {
+            return Some(value);
+        }
+
+        let main_idx = usize::try_from(idx >> self.idx_bits).ok()?;
+        let mut lit_idx = (idx & ((1 << self.idx_bits) - 1)) as i64 - (1 << (self.idx_bits - 1));
+
+        let entry = self.index_table.checked_add(main_idx.checked_mul(6)?)?;
+        let mut block = read_u32(bytes, entry)? as usize;
+        lit_idx += read_u16(bytes, entry + 4)? as i64;
+
+        let block_len = |block: usize| -> Option<i64> {
+            Some(read_u16(bytes, self.size_table.checked_add(2 * block)?)? as i64)
+        };
+
+        while lit_idx < 0 {
+            block = block.checked_sub(1)?;
+            lit_idx += block_len(block)? + 1;
+        }
+
+        while lit_idx > block_len(block)? {
+            lit_idx -= block_len(block)? + 1;
+            block += 1;
+        }
+
+        if block >= self.real_num_blocks {
+            return None;
+        }
+
+        let mut at = self.data.checked_add(block << self.block_size)?;
+        let mut code = read_be(bytes, at, 8);
+        let mut bit_count = 0;
+        at += 8;
+
+        // Read codes until the one whose symbol holds the value
+        let mut sym = loop {
+            let l = self.base.iter().position(|base| code >= *base)?;
+            let len = self.min_len + l as u32;
+
+            let first = read_u16(bytes, self.offset + 2 * l)? as u64;
+            let sym = (first + ((code - self.base[l]) >> (64 - len))) as usize;
+            let sym_len = *self.sym_len.get(sym)? as i64;
+
+            if lit_idx <= sym_len {
+                break sym;
+            }
+
+            lit_idx -= sym_len + 1;
+            code <<= len;
+            bit_count += len;
+
+            if bit_count >= 32 {
+                bit_count -= 32;
+                code |= read_be(bytes, at, 4) << bit_count;
+                at += 4;
+            }
+        };
+
+        // and then go down the pairs it stands for to the value
+        while let Some((left, right)) = self.pair(bytes, sym)? {
+            let left_len = *self.sym_len.get(left)? as i64;
+            let next = if lit_idx <= left_len {
+                left
+            } else {
+                lit_idx -= left_len + 1;
+                right
+            };
+
+            if *self.sym_len.get(next)? >= self.sym_len[sym] {
+                return None;
+            }
+
+            sym = next;
+        }
+
+        let w = bytes.get(self.sym_pat + 3 * sym..self.sym_pat + 3 * sym + 2)?;
+
+        Some(w[0] as u16 | ((w[1] as u16 & 0xf) << 8))
+    }
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    let bytes = bytes.get(at..at.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let bytes = bytes.get(at..at.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/* Reads `len` bytes from `at` on as a big-endian number, with zeros past
+ * the end of the file.
+ */
+fn read_be(bytes: &[u8], at: usize, len: usize) -> u64 {
+    (0..len).fold(0, |value, i| {
+        let byte = at.checked_add(i).and_then(|at| bytes.get(at)).copied();
+        (value << 8) | byte.unwrap_or(0) as u64
+    })
+}
@@ -0,0 +1,115 @@
+use chess_rs::bitboard::{self, Bitboard};
+use chess_rs::{Colour, Square};
+
+/* The squares reached from `sq` by single steps of (file, rank), or by
+ * sliding along them up to and including the first occupied square.
+ */
+fn walk(sq: Square, steps: &[(i8, i8)], occupied: Bitboard, slide: bool) -> Bitboard {
+    let mut attacks = 0;
+
+    for &(df, dr) in steps {
+        let mut to = sq;
+
+        while let Some(next) = to.offset(df, dr) {
+            to = next;
+            attacks |= bitboard::bit(to);
+
+            if !slide || occupied & bitboard::bit(to) != 0 {
+                break;
+            }
+        }
+    }
+
+    attacks
+}
+
+const KNIGHT: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const DIAGONAL: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ORTHOGONAL: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+#[test]
+fn squares_of_a_bitboard() {
+    let squares: Vec<usize> = bitboard::Squares(0x8000_0000_0000_1001)
+        .map(Square::index)
+        .collect();
+    assert_eq!(squares, [0, 12, 63]);
+}
+
+#[test]
+fn step_tables() {
+    let king: Vec<(i8, i8)> = DIAGONAL.iter().chain(&ORTHOGONAL).copied().collect();
+
+    for sq in 0..64 {
+        let sq = Square::from_index(sq);
+
+        assert_eq!(bitboard::knight_attacks(sq), walk(sq, &KNIGHT, 0, false));
+        assert_eq!(bitboard::king_attacks(sq), walk(sq, &king, 0, false));
+        assert_eq!(
+            bitboard::pawn_attacks(Colour::White, sq),
+            walk(sq, &[(-1, 1), (1, 1)], 0, false)
+        );
+        assert_eq!(
+            bitboard::pawn_attacks(Colour::Black, sq),
+            walk(sq, &[(-1, -1), (1, -1)], 0, false)
+        );
+    }
+}
+
+#[test]
+fn sliders_stop_at_the_first_piece() {
+    let mut state: u64 = 1;
+
+    for _ in 0..200 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        let occupied: Bitboard = state & (state >> 11);
+
+        for sq in 0..64 {
+            let sq = Square::from_index(sq);
+
+            let bishop = walk(sq, &DIAGONAL, occupied, true);
+            let rook = walk(sq, &ORTHOGONAL, occupied, true);
+
+            assert_eq!(bitboard::bishop_attacks(sq, occupied), bishop);
+            assert_eq!(bitboard::rook_attacks(sq, occupied), rook);
+            assert_eq!(bitboard::queen_attacks(sq, occupied), bishop | rook);
+        }
+    }
+}
+
+#[test]
+fn magics_match_the_rays() {
+    let mut state: u64 = 1;
+
+    for _ in 0..2000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        let occupied: Bitboard = state & (state >> 11);
+
+        for sq in 0..64 {
+            let sq = Square::from_index(sq);
+
+            assert_eq!(
+                bitboard::bishop_attacks(sq, occupied),
+                bitboard::bishop_rays(sq, occupied)
+            );
+            assert_eq!(
+                bitboard::rook_attacks(sq, occupied),
+                bitboard::rook_rays(sq, occupied)
+            );
+        }
+    }
+}
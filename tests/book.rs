@@ -0,0 +1,89 @@
+use chess_rs::search::Limit;
+use chess_rs::{Book, Engine, Position, Selection, Square};
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+/* A book entry the way it's stored in a `.bin` file.
+ */
+fn entry(key: u64, from: &str, to: &str, promotion: u16, weight: u16) -> Vec<u8> {
+    let (from, to) = (sq(from), sq(to));
+    let mv = promotion << 12
+        | (from.rank() as u16) << 9
+        | (from.file() as u16) << 6
+        | (to.rank() as u16) << 3
+        | to.file() as u16;
+
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&mv.to_be_bytes());
+    bytes.extend_from_slice(&weight.to_be_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes
+}
+
+#[test]
+fn book_moves() {
+    let start = Position::new();
+    let castling = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+    let mut bytes = Vec::new();
+    bytes.extend(entry(0x0123, "a2", "a3", 0, 1));
+    bytes.extend(entry(start.hash(), "e2", "e4", 0, 30));
+    bytes.extend(entry(start.hash(), "d2", "d4", 0, 10));
+    bytes.extend(entry(start.hash(), "e2", "e5", 0, 50));
+    bytes.extend(entry(start.hash(), "g1", "f3", 0, 0));
+    bytes.extend(entry(castling.hash(), "e1", "h1", 0, 1));
+    bytes.extend(entry(castling.hash(), "e1", "a1", 0, 2));
+
+    let mut entries: Vec<&[u8]> = bytes.chunks(16).collect();
+    entries.sort();
+    let book = Book::from_bytes(&entries.concat()).unwrap();
+    assert_eq!(book.len(), 7);
+
+    // The illegal e2e5 is left out
+    let moves: Vec<(String, u16)> = book
+        .moves(&start)
+        .into_iter()
+        .map(|(mv, weight)| (mv.to_string(), weight))
+        .collect();
+    assert_eq!(
+        moves,
+        [("e2e4".into(), 30), ("d2d4".into(), 10), ("g1f3".into(), 0)]
+    );
+
+    // Castling is stored as the king taking its rook
+    let castles: Vec<String> = book
+        .moves(&castling)
+        .iter()
+        .map(|(mv, _)| castling.san(mv))
+        .collect();
+    assert_eq!(castles, ["O-O-O", "O-O"]);
+
+    assert_eq!(
+        book.choose(&start, Selection::Best).unwrap().to_string(),
+        "e2e4"
+    );
+
+    for _ in 0..20 {
+        let mv = book.choose(&start, Selection::Weighted).unwrap();
+        assert!(["e2e4", "d2d4"].contains(&mv.to_string().as_str()));
+    }
+
+    let elsewhere = Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert!(book.moves(&elsewhere).is_empty());
+    assert!(book.choose(&elsewhere, Selection::Weighted).is_none());
+
+    let mut engine = Engine::new();
+    engine.set_book(book, Selection::Best);
+    let result = engine.think(&start, Limit::Depth(3));
+    assert_eq!(result.best_move.unwrap().to_string(), "e2e4");
+    assert_eq!((result.depth, result.nodes), (0, 0));
+}
+
+#[test]
+fn broken_books() {
+    assert!(Book::from_bytes(&[0; 15]).is_err());
+    assert!(Book::from_bytes(&[]).unwrap().is_empty());
+    assert!(Book::open("no/such/book.bin").is_err());
+}
@@ -0,0 +1,100 @@
+use std::collections::HashSet;
+
+use chess_rs::{CastlingNotation, Colour, MoveKind, Piece, PieceKind, Position, Square};
+
+/* The back rank of white's pieces as FEN letters, a-file first.
+ */
+fn back_rank(pos: &Position) -> String {
+    (0..8)
+        .map(|x| pos.piece_at(Square::new(x, 0)).map_or('-', Piece::to_char))
+        .collect()
+}
+
+#[test]
+fn starting_positions() {
+    assert_eq!(Position::chess960(518).board(), Position::new().board());
+    assert_eq!(back_rank(&Position::chess960(0)), "BBQNNRKR");
+
+    let mut rows = HashSet::new();
+
+    for index in 0..960 {
+        let row = back_rank(&Position::chess960(index));
+        let file = |pc| row.find(pc).unwrap();
+        let last = |pc| row.rfind(pc).unwrap();
+
+        assert!(file('R') < file('K') && file('K') < last('R'));
+        assert_ne!(file('B') % 2, last('B') % 2);
+
+        rows.insert(row);
+    }
+
+    assert_eq!(rows.len(), 960);
+}
+
+#[test]
+fn castling_notation() {
+    let mut pos = Position::chess960(0);
+    assert_eq!(pos.castling_rights(CastlingNotation::XFen), "KQkq");
+    assert_eq!(pos.castling_rights(CastlingNotation::Shredder), "HFhf");
+
+    pos.set_castling_rights("Fh").unwrap();
+    assert_eq!(pos.castling(), 0b1001);
+
+    // Another rook between the king and the a-file rook
+    let mut board = *Position::new().board();
+    let rook = Some(Piece::new(Colour::White, PieceKind::Rook));
+
+    for x in 0..8 {
+        board[Square::new(x, 0).index()] = None;
+    }
+
+    board[Square::new(0, 0).index()] = rook;
+    board[Square::new(1, 0).index()] = rook;
+    board[Square::new(4, 0).index()] = Some(Piece::new(Colour::White, PieceKind::King));
+
+    let mut pos = Position::from_board(board, Colour::White, 0);
+    pos.set_castling_rights("B").unwrap();
+    assert_eq!(pos.castling_rights(CastlingNotation::XFen), "B");
+
+    pos.set_castling_rights("Q").unwrap();
+    assert_eq!(pos.castling_rights(CastlingNotation::XFen), "Q");
+    assert_eq!(pos.castling_rights(CastlingNotation::Shredder), "A");
+
+    assert!(pos.set_castling_rights("K").is_err());
+}
+
+#[test]
+fn castling_onto_the_rook() {
+    // King on f1 and rook on g1: castling king side swaps them
+    let sq = |name: &str| name.parse::<Square>().unwrap();
+    let king = Some(Piece::new(Colour::White, PieceKind::King));
+    let rook = Some(Piece::new(Colour::White, PieceKind::Rook));
+
+    let mut board = [None; 64];
+    board[sq("e8").index()] = Some(Piece::new(Colour::Black, PieceKind::King));
+    board[sq("f1").index()] = king;
+    board[sq("g1").index()] = rook;
+
+    let mut pos = Position::from_board(board, Colour::White, 0);
+    pos.set_castling_rights("G").unwrap();
+    assert!(pos.is_chess960());
+
+    let mv = pos
+        .legal_moves()
+        .into_iter()
+        .find(|mv| mv.kind == MoveKind::CastleKingSide)
+        .unwrap();
+
+    assert_eq!(mv.to, sq("g1"));
+    assert_eq!(pos.castling_rook(&mv), Some(sq("g1")));
+
+    let hash = pos.hash();
+    let undo = pos.make_move(&mv);
+    assert_eq!(pos.piece_at(sq("g1")), king);
+    assert_eq!(pos.piece_at(sq("f1")), rook);
+
+    pos.unmake_move(&mv, undo);
+    assert_eq!(pos.piece_at(sq("f1")), king);
+    assert_eq!(pos.piece_at(sq("g1")), rook);
+    assert_eq!(pos.hash(), hash);
+}
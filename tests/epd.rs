@@ -0,0 +1,106 @@
+use chess_rs::{Epd, EpdError, FenError, ParseSanError, Position};
+
+const WAC_001: &str =
+    "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\";";
+
+#[test]
+fn reading_records() {
+    let epd: Epd = WAC_001.parse().unwrap();
+    assert_eq!(epd.id(), Some("WAC.001"));
+    assert_eq!(epd.operation("bm"), Some(&[String::from("Qg6")][..]));
+    assert_eq!(epd.moves("bm").unwrap()[0].to_string(), "g3g6");
+    assert!(epd.moves("am").unwrap().is_empty());
+    assert_eq!(epd.position.halfmove(), 0);
+    assert_eq!(epd.position.fullmove(), 1);
+
+    let epd: Epd =
+        "4k3/8/8/8/8/8/8/4K2R w K - hmvc 12; fmvn 40; c0 \"a; quoted, comment\"; acd 9; ce -35"
+            .parse()
+            .unwrap();
+    assert_eq!(epd.position.halfmove(), 12);
+    assert_eq!(epd.position.fullmove(), 40);
+    assert_eq!(
+        epd.operation("c0"),
+        Some(&[String::from("a; quoted, comment")][..])
+    );
+    assert_eq!(epd.number("acd"), Some(9));
+    assert_eq!(epd.number("ce"), Some(-35));
+    assert_eq!(epd.operations.len(), 5);
+
+    let epd: Epd = "4k3/8/8/8/8/8/8/4K2R w K - bm O-O Rh8+;".parse().unwrap();
+    assert_eq!(epd.moves("bm").unwrap().len(), 2);
+}
+
+#[test]
+fn move_points() {
+    let epd: Epd = "4k3/1P6/8/8/8/8/8/4K3 w - - bm b8=Q+; c0 \"b8=Q+=10, b8=R=5, Kd2=1\";"
+        .parse()
+        .unwrap();
+    let points = epd.move_points().unwrap();
+    assert_eq!(
+        points,
+        [
+            (String::from("b8=Q+"), 10),
+            (String::from("b8=R"), 5),
+            (String::from("Kd2"), 1)
+        ]
+    );
+    assert!(points
+        .iter()
+        .all(|(san, _)| epd.position.parse_san(san).is_ok()));
+
+    let epd: Epd = WAC_001.parse().unwrap();
+    assert_eq!(epd.move_points(), None);
+
+    let epd: Epd = "4k3/8/8/8/8/8/8/4K2R w K - c0 \"just a comment\";"
+        .parse()
+        .unwrap();
+    assert_eq!(epd.move_points(), None);
+}
+
+#[test]
+fn writing_records() {
+    let epd: Epd = WAC_001.parse().unwrap();
+    assert_eq!(epd.to_string(), WAC_001);
+
+    let mut epd = Epd::new(Position::new());
+    epd.set_operation("id", vec![String::from("start")]);
+    epd.set_operation("pm", vec![String::from("e4")]);
+    epd.set_operation("c1", vec![String::from("two words")]);
+    epd.set_operation("pm", vec![String::from("d4")]);
+    assert_eq!(
+        epd.to_string(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"start\"; pm d4; c1 \"two words\";"
+    );
+
+    let read: Epd = epd.to_string().parse().unwrap();
+    assert_eq!(read.operations, epd.operations);
+}
+
+#[test]
+fn bad_records() {
+    let error = |line: &str| match line.parse::<Epd>() {
+        Ok(_) => panic!("{} was read", line),
+        Err(e) => e,
+    };
+
+    assert!(matches!(error(""), EpdError::Fen(_)));
+    assert!(matches!(
+        error("8/8/8/8/8/8/8/8 w - - id \"no kings\";"),
+        EpdError::Fen(FenError::Invalid(_))
+    ));
+
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+    for ops in &["id \"open;", "; bm e4;", "1x e4;", "hmvc x;", "fmvn;"] {
+        assert_eq!(
+            error(&format!("{} {}", start, ops)),
+            EpdError::Operation,
+            "{}",
+            ops
+        );
+    }
+
+    let epd: Epd = format!("{} bm e5; am Nf3;", start).parse().unwrap();
+    assert_eq!(epd.moves("bm").unwrap_err(), ParseSanError::Illegal);
+    assert!(epd.moves("am").is_ok());
+}
@@ -0,0 +1,107 @@
+use chess_rs::{Colour, FenError, InvalidPosition, Position, Square};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+#[test]
+fn round_trips() {
+    assert_eq!(Position::new().to_fen(), START);
+
+    let pos = Position::from_fen(START).unwrap();
+    assert_eq!(pos.hash(), Position::new().hash());
+
+    for fen in &[
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9",
+        "rk2r2r/8/8/8/8/8/8/RK2R2R w Ee - 0 1",
+    ] {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn counters_and_en_passant() {
+    let mut pos = Position::new();
+    let mv = pos.find_move(sq("e2"), sq("e4"), None).unwrap();
+    pos.make_move(&mv);
+
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    assert_eq!(pos.to_fen(), fen);
+
+    let read = Position::from_fen(fen).unwrap();
+    assert_eq!(read.hash(), pos.hash());
+    assert_eq!(read.enp_w(), pos.enp_w());
+
+    // EPD leaves the counters out
+    let pos = Position::from_fen("8/8/4k3/8/8/4K3/8/8 b - -").unwrap();
+    assert_eq!(pos.player(), Colour::Black);
+    assert_eq!((pos.halfmove(), pos.fullmove()), (0, 1));
+}
+
+#[test]
+fn shredder_castling() {
+    let pos = Position::from_fen("bqnb1rkr/8/8/8/8/8/8/BQNB1RKR w HFhf - 0 1").unwrap();
+
+    assert!(pos.is_chess960());
+    assert_eq!(pos.castling(), 0b1111);
+}
+
+#[test]
+fn errors() {
+    let errors = [
+        ("", FenError::Placement),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            FenError::Placement,
+        ),
+        (
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::Placement,
+        ),
+        (
+            "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::Placement,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            FenError::SideToMove,
+        ),
+        (
+            "rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            FenError::Castling,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+            FenError::EnPassant,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            FenError::Counter,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1",
+            FenError::Invalid(vec![InvalidPosition::EnPassant]),
+        ),
+        (
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            FenError::Invalid(vec![
+                InvalidPosition::KingCount(Colour::White, 0),
+                InvalidPosition::KingCount(Colour::Black, 0),
+            ]),
+        ),
+    ];
+
+    for (fen, error) in errors.iter() {
+        assert_eq!(
+            Position::from_fen(fen).err().as_ref(),
+            Some(error),
+            "{}",
+            fen
+        );
+    }
+}
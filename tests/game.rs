@@ -0,0 +1,199 @@
+use chess_rs::{Colour, DrawReason, DrawRule, Game, Outcome, Position};
+
+fn position(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap()
+}
+
+fn game(fen: &str, draw_rule: DrawRule) -> Game {
+    Game::from_position(position(fen), draw_rule)
+}
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for san in moves {
+        let mv = game.position().parse_san(san).unwrap();
+        game.play(&mv).unwrap();
+    }
+}
+
+const SHUFFLE: [&str; 4] = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+#[test]
+fn fifty_moves() {
+    let rook = "8/8/4k3/8/8/4K3/8/R7 w - - 99 60";
+
+    let mut automatic = game(rook, DrawRule::Automatic);
+    play(&mut automatic, &["Ra2"]);
+    assert_eq!(
+        automatic.outcome(),
+        Some(Outcome::Draw(DrawReason::FiftyMoves))
+    );
+
+    // Under the claim rule the game goes on until someone claims
+    let mut claim = game(rook, DrawRule::Claim);
+    assert!(!claim.claim_draw());
+    assert_eq!(claim.outcome(), None);
+
+    play(&mut claim, &["Ra2"]);
+    assert_eq!(claim.outcome(), None);
+    assert!(claim.claim_draw());
+    assert_eq!(claim.outcome(), Some(Outcome::Draw(DrawReason::FiftyMoves)));
+    assert!(!claim.claim_draw());
+
+    // A capture or pawn move starts the count again
+    let mut reset = game("8/8/4k3/8/8/4K3/P7/R7 w - - 99 60", DrawRule::Automatic);
+    play(&mut reset, &["a3"]);
+    assert_eq!(reset.outcome(), None);
+    assert_eq!(reset.position().halfmove(), 0);
+}
+
+#[test]
+fn seventy_five_moves() {
+    let mut claim = game("8/8/4k3/8/8/4K3/8/R7 w - - 149 90", DrawRule::Claim);
+    play(&mut claim, &["Ra2"]);
+    assert_eq!(
+        claim.outcome(),
+        Some(Outcome::Draw(DrawReason::SeventyFiveMoves))
+    );
+}
+
+#[test]
+fn threefold_repetition() {
+    let mut automatic = Game::new(DrawRule::Automatic);
+    play(&mut automatic, &SHUFFLE);
+    assert_eq!(automatic.repetitions(), 2);
+    assert_eq!(automatic.outcome(), None);
+
+    play(&mut automatic, &SHUFFLE);
+    assert_eq!(automatic.repetitions(), 3);
+    assert_eq!(
+        automatic.outcome(),
+        Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+    );
+
+    let mut claim = Game::new(DrawRule::Claim);
+    play(&mut claim, &SHUFFLE);
+    assert!(!claim.claim_draw());
+    assert_eq!(claim.outcome(), None);
+
+    play(&mut claim, &SHUFFLE);
+    assert_eq!(claim.outcome(), None);
+    assert!(claim.claim_draw());
+    assert_eq!(
+        claim.outcome(),
+        Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+    );
+}
+
+#[test]
+fn fivefold_repetition() {
+    let mut claim = Game::new(DrawRule::Claim);
+
+    for _ in 0..3 {
+        play(&mut claim, &SHUFFLE);
+    }
+    assert_eq!(claim.repetitions(), 4);
+    assert_eq!(claim.outcome(), None);
+
+    play(&mut claim, &SHUFFLE);
+    assert_eq!(claim.repetitions(), 5);
+    assert_eq!(
+        claim.outcome(),
+        Some(Outcome::Draw(DrawReason::FivefoldRepetition))
+    );
+}
+
+#[test]
+fn repetitions_since_the_last_pawn_move() {
+    let mut automatic = Game::new(DrawRule::Automatic);
+    play(&mut automatic, &SHUFFLE);
+    play(&mut automatic, &["e4", "e5"]);
+    assert_eq!(automatic.repetitions(), 1);
+
+    play(&mut automatic, &SHUFFLE);
+    assert_eq!(automatic.repetitions(), 2);
+    play(&mut automatic, &SHUFFLE[..2]);
+    assert_eq!(automatic.repetitions(), 2);
+    assert_eq!(automatic.outcome(), None);
+
+    // Only the positions since the move counter was reset are looked at
+    let mut counted = game(
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+        DrawRule::Automatic,
+    );
+    play(&mut counted, &SHUFFLE);
+    play(&mut counted, &SHUFFLE);
+    assert_eq!(
+        counted.outcome(),
+        Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+    );
+}
+
+#[test]
+fn claims_under_the_automatic_rule() {
+    let mut automatic = game("8/8/4k3/8/8/4K3/8/R7 w - - 120 80", DrawRule::Automatic);
+    assert!(!automatic.claim_draw());
+    assert_eq!(automatic.outcome(), None);
+}
+
+#[test]
+fn insufficient_material() {
+    let dead = [
+        "8/8/4k3/8/8/4K3/8/8 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/2B5 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/2n5 w - - 0 1",
+        // Bishops on the same colour can never attack the other king
+        "5b2/8/4k3/8/8/4K3/8/2B5 w - - 0 1",
+    ];
+
+    for fen in &dead {
+        assert!(position(fen).is_dead(), "{}", fen);
+    }
+
+    let alive = [
+        "2b5/8/4k3/8/8/4K3/8/2B5 w - - 0 1",
+        "2b5/8/4k3/8/8/4K3/8/2N5 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/1NN5 w - - 0 1",
+        "8/8/4k3/8/8/4K3/P7/8 w - - 0 1",
+    ];
+
+    for fen in &alive {
+        assert!(!position(fen).is_dead(), "{}", fen);
+    }
+
+    // A lone knight can only mate with an enemy piece blocking the king
+    let knights = position("2b5/8/4k3/8/8/4K3/8/2N5 w - - 0 1");
+    assert!(knights.has_mating_material(Colour::White));
+    assert!(knights.has_mating_material(Colour::Black));
+
+    let two_knights = position("8/8/4k3/8/8/4K3/8/1NN5 w - - 0 1");
+    assert!(two_knights.has_mating_material(Colour::White));
+    assert!(!two_knights.has_mating_material(Colour::Black));
+
+    // Taking the last piece ends the game
+    let mut game = game("8/8/4k3/8/8/4K3/4r3/8 w - - 0 1", DrawRule::Automatic);
+    play(&mut game, &["Kxe2"]);
+    assert_eq!(
+        game.outcome(),
+        Some(Outcome::Draw(DrawReason::InsufficientMaterial))
+    );
+}
+
+#[test]
+fn timeouts() {
+    let rook = "8/8/4k3/8/8/4K3/8/R7 w - - 0 1";
+
+    let mut bare_king = game(rook, DrawRule::Automatic);
+    bare_king.timeout(Colour::White);
+    assert_eq!(
+        bare_king.outcome(),
+        Some(Outcome::Draw(DrawReason::TimeoutVsInsufficientMaterial))
+    );
+
+    let mut lost = game(rook, DrawRule::Automatic);
+    lost.timeout(Colour::Black);
+    assert_eq!(lost.outcome(), Some(Outcome::WhiteWins));
+
+    // A finished game stays finished
+    lost.timeout(Colour::White);
+    assert_eq!(lost.outcome(), Some(Outcome::WhiteWins));
+}
@@ -0,0 +1,70 @@
+use chess_rs::bitboard;
+use chess_rs::{MoveKind, PieceKind, Position, Square};
+
+fn position(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap()
+}
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+#[test]
+fn pinned_pieces_stay_on_the_line() {
+    let pos = position("4r2k/8/8/b7/1R6/8/4B3/4K3 w - - 0 1");
+
+    assert_eq!(
+        pos.pinned(),
+        bitboard::bit(sq("e2")) | bitboard::bit(sq("b4"))
+    );
+    assert!(pos.piece_moves(sq("e2")).is_empty());
+
+    // A rook pinned on a diagonal can't move at all
+    assert!(pos.piece_moves(sq("b4")).is_empty());
+}
+
+#[test]
+fn check_evasions() {
+    // Rook check along the file: block, take or step aside
+    let pos = position("k3r3/8/8/7B/8/2N5/8/4K3 w - - 0 1");
+
+    assert_eq!(pos.checkers(), bitboard::bit(sq("e8")));
+
+    let mut moves: Vec<String> = pos.legal_moves().iter().map(|mv| mv.to_string()).collect();
+    moves.sort();
+    assert_eq!(
+        moves,
+        ["c3e2", "c3e4", "e1d1", "e1d2", "e1f1", "e1f2", "h5e2", "h5e8"]
+    );
+
+    // Double check: only the king moves
+    let pos = position("4r2k/8/8/8/Q7/3n4/8/4K3 w - - 0 1");
+
+    assert_eq!(pos.checkers().count_ones(), 2);
+    assert!(pos
+        .legal_moves()
+        .iter()
+        .all(|mv| mv.piece.kind == PieceKind::King));
+}
+
+#[test]
+fn en_passant_along_the_rank() {
+    // Taking d6 en passant would take both pawns off the 5th rank and
+    // leave the king facing the rook
+    let mut pos = position("8/3p4/8/K3P2r/8/8/8/7k b - - 0 1");
+
+    let push = pos.find_move(sq("d7"), sq("d5"), None).unwrap();
+    pos.make_move(&push);
+
+    assert_eq!(pos.pinned(), 0);
+    assert!(pos
+        .legal_moves()
+        .iter()
+        .all(|mv| mv.kind != MoveKind::EnPassant));
+
+    // Without the rook it's fine
+    let mut pos = position("8/3p4/8/K3P3/8/8/8/7k b - - 0 1");
+
+    pos.make_move(&push);
+    assert!(pos.find_move(sq("e5"), sq("d6"), None).is_some());
+}
@@ -0,0 +1,135 @@
+use chess_rs::bitboard::Bitboard;
+use chess_rs::{Colour, Piece, Position};
+
+/* Everything about a position that `make_move` changes and `unmake_move`
+ * has to put back.
+ */
+#[derive(PartialEq, Debug)]
+struct Snapshot {
+    board: [Option<Piece>; 64],
+    pieces: Vec<Bitboard>,
+    colours: [Bitboard; 2],
+    player: Colour,
+    castling: u8,
+    enp_w: u8,
+    enp_b: u8,
+    halfmove: u16,
+    fullmove: u16,
+    hash: u64,
+}
+
+fn snapshot(position: &Position) -> Snapshot {
+    Snapshot {
+        board: *position.board(),
+        pieces: "PNBRQKpnbrqk"
+            .chars()
+            .map(|ch| position.pieces(Piece::from_char(ch).unwrap()))
+            .collect(),
+        colours: [
+            position.colour(Colour::White),
+            position.colour(Colour::Black),
+        ],
+        player: position.player(),
+        castling: position.castling(),
+        enp_w: position.enp_w(),
+        enp_b: position.enp_b(),
+        halfmove: position.halfmove(),
+        fullmove: position.fullmove(),
+        hash: position.hash(),
+    }
+}
+
+/* Makes and unmakes every legal move, and every reply to it, checking
+ * that the position comes back exactly as it was.
+ */
+fn round_trip(position: &mut Position, depth: u32) {
+    let before = snapshot(position);
+
+    for mv in position.legal_moves() {
+        let undo = position.make_move(&mv);
+
+        if depth > 1 {
+            round_trip(position, depth - 1);
+        }
+
+        position.unmake_move(&mv, undo);
+        assert_eq!(snapshot(position), before, "{}", mv);
+    }
+}
+
+/* The FEN letter of the piece on the square called `name`.
+ */
+fn piece(position: &Position, name: &str) -> Option<char> {
+    position.piece_at(name.parse().unwrap()).map(Piece::to_char)
+}
+
+/* Runs the round trip in every position along a line of moves from
+ * `position`, written as the two squares like `g1f3`.
+ */
+fn round_trips_along(mut position: Position, line: &[&str]) -> Position {
+    for text in line {
+        round_trip(&mut position, 2);
+
+        let mv = position
+            .legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == *text)
+            .unwrap();
+        position.make_move(&mv);
+    }
+
+    round_trip(&mut position, 2);
+    position
+}
+
+#[test]
+fn castling() {
+    let position = round_trips_along(
+        Position::new(),
+        &[
+            "e2e4", "e7e5", "g1f3", "g8f6", "f1c4", "f8c5", "d2d3", "d7d6", "b1c3", "b8c6", "c1e3",
+            "c8e6", "d1d2", "d8d7", "e1g1", "e8c8",
+        ],
+    );
+
+    assert_eq!(piece(&position, "g1"), Some('K'));
+    assert_eq!(piece(&position, "c8"), Some('k'));
+    assert_eq!(position.castling(), 0);
+}
+
+#[test]
+fn en_passant() {
+    let position = round_trips_along(
+        Position::new(),
+        &[
+            "e2e4", "c7c5", "e4e5", "c5c4", "g1f3", "d7d5", "e5d6", "e7d6", "b2b4", "c4b3",
+        ],
+    );
+
+    assert_eq!(piece(&position, "b3"), Some('p'));
+    assert_eq!(piece(&position, "b4"), None);
+}
+
+#[test]
+fn promotion() {
+    let position = round_trips_along(
+        Position::new(),
+        &[
+            "a2a4", "b7b5", "a4b5", "a7a6", "b5a6", "c8b7", "a6b7", "b8c6", "b7a8q",
+        ],
+    );
+
+    assert_eq!(piece(&position, "a8"), Some('Q'));
+}
+
+#[test]
+fn chess960_castling() {
+    // BBQNRNKR, where castling king side leaves the king on g1 and only
+    // moves the rook
+    let position = round_trips_along(Position::chess960(96), &["f1e3", "f8e6", "g1g1", "g8g8"]);
+
+    assert_eq!(piece(&position, "f1"), Some('R'));
+    assert_eq!(piece(&position, "h1"), None);
+    assert_eq!(piece(&position, "f8"), Some('r'));
+    assert_eq!(piece(&position, "h8"), None);
+}
@@ -0,0 +1,128 @@
+//! Node counts of the standard perft positions, taken from
+//! https://www.chessprogramming.org/Perft_Results and Martin Sedlak's
+//! collection of move generator edge cases. The Chess960 positions come
+//! from the Chess960 Perft Results page of the same wiki.
+
+use chess_rs::Position;
+
+fn position(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap()
+}
+
+fn assert_perft(position: &Position, counts: &[u64]) {
+    for (i, nodes) in counts.iter().enumerate() {
+        let depth = i as u32 + 1;
+        assert_eq!(position.perft(depth), *nodes, "depth {}", depth);
+    }
+}
+
+#[test]
+fn start_position() {
+    assert_perft(&Position::new(), &[20, 400, 8902, 197_281]);
+}
+
+#[test]
+fn kiwipete() {
+    let pos = position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+
+    assert_perft(&pos, &[48, 2039, 97_862]);
+}
+
+#[test]
+fn rook_endgame() {
+    let pos = position("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+
+    assert_perft(&pos, &[14, 191, 2812, 43_238, 674_624]);
+}
+
+#[test]
+fn promotions_and_castling() {
+    let pos = position("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+
+    assert_perft(&pos, &[6, 264, 9467, 422_333]);
+}
+
+#[test]
+fn promotion_with_a_bishop_pinned() {
+    let pos = position("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 0 1");
+
+    assert_perft(&pos, &[44, 1486, 62_379]);
+}
+
+#[test]
+fn en_passant_exposing_the_king() {
+    let pos = position("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1");
+    assert_eq!(pos.perft(6), 1_134_888);
+
+    let pos = position("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1");
+    assert_eq!(pos.perft(6), 1_015_133);
+}
+
+#[test]
+fn castling_gives_check() {
+    let pos = position("5k2/8/8/8/8/8/8/4K2R w K - 0 1");
+    assert_eq!(pos.perft(6), 661_072);
+
+    let pos = position("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1");
+    assert_eq!(pos.perft(6), 803_711);
+}
+
+#[test]
+fn castling_rights() {
+    let pos = position("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1");
+    assert_eq!(pos.perft(4), 1_274_206);
+
+    let pos = position("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1");
+    assert_eq!(pos.perft(4), 1_720_476);
+}
+
+#[test]
+fn promotions() {
+    let pos = position("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1");
+    assert_eq!(pos.perft(6), 3_821_001);
+
+    let pos = position("4k3/1P6/8/8/8/8/K7/8 w - - 0 1");
+    assert_eq!(pos.perft(6), 217_342);
+
+    let pos = position("8/P1k5/K7/8/8/8/8/8 w - - 0 1");
+    assert_eq!(pos.perft(6), 92_683);
+}
+
+#[test]
+fn discovered_check() {
+    let pos = position("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1");
+    assert_eq!(pos.perft(5), 1_004_658);
+}
+
+#[test]
+fn stalemate_and_checkmate() {
+    let pos = position("K1k5/8/P7/8/8/8/8/8 w - - 0 1");
+    assert_eq!(pos.perft(6), 2217);
+
+    let pos = position("8/k1P5/8/1K6/8/8/8/8 w - - 0 1");
+    assert_eq!(pos.perft(7), 567_584);
+
+    let pos = position("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1");
+    assert_eq!(pos.perft(4), 23_527);
+}
+
+#[test]
+fn chess960() {
+    let pos = position("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 0 1");
+    assert_perft(&pos, &[21, 528, 12_189, 326_672]);
+
+    let pos = position("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 0 1");
+    assert_perft(&pos, &[21, 807, 18_002, 667_366]);
+
+    let pos = position("b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 0 1");
+    assert_perft(&pos, &[20, 479, 10_471, 273_318]);
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let pos = Position::new();
+    let divided = pos.divide(3);
+
+    assert_eq!(divided.len(), 20);
+    assert_eq!(divided.iter().map(|(_, n)| n).sum::<u64>(), pos.perft(3));
+}
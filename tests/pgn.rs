@@ -0,0 +1,209 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use chess_rs::{pgn, Colour, DrawRule, Game, Outcome, PgnError, Position, Square};
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+fn error(text: &str) -> PgnError {
+    match pgn::read(text) {
+        Ok(_) => panic!("{} was read", text),
+        Err(e) => e,
+    }
+}
+
+fn play(game: &mut Game, moves: &[(&str, &str)]) {
+    for (from, to) in moves {
+        let mv = game.position().find_move(sq(from), sq(to), None).unwrap();
+        game.play(&mv).unwrap();
+    }
+}
+
+#[test]
+fn export_format() {
+    let mut game = Game::new(DrawRule::Automatic);
+    game.set_tag("White", "Anderssen, \"The Immortal\"");
+    game.set_tag("Date", "1851.06.21");
+
+    play(
+        &mut game,
+        &[
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("d1", "h5"),
+            ("b8", "c6"),
+            ("f1", "c4"),
+            ("g8", "f6"),
+            ("h5", "f7"),
+        ],
+    );
+
+    let pgn = "[Event \"?\"]\n\
+               [Site \"?\"]\n\
+               [Date \"1851.06.21\"]\n\
+               [Round \"?\"]\n\
+               [White \"Anderssen, \\\"The Immortal\\\"\"]\n\
+               [Black \"?\"]\n\
+               [Result \"1-0\"]\n\
+               [TimeControl \"-\"]\n\
+               \n\
+               1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n";
+
+    assert_eq!(game.to_pgn(), pgn);
+}
+
+#[test]
+fn set_up_positions() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 3 20";
+    let mut game = Game::from_position(Position::from_fen(fen).unwrap(), DrawRule::Automatic);
+    play(&mut game, &[("e8", "d7"), ("e2", "e4")]);
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Result \"*\"]\n[TimeControl \"-\"]\n[SetUp \"1\"]\n"));
+    assert!(pgn.contains(&format!("[FEN \"{}\"]\n", fen)));
+    assert!(pgn.ends_with("\n20... Kd7 21. e4 *\n"));
+
+    let game = Game::from_position(Position::chess960(0), DrawRule::Automatic);
+    assert!(game.to_pgn().contains("[Variant \"Chess960\"]\n"));
+}
+
+#[test]
+fn long_games_are_wrapped() {
+    let mut game = Game::new(DrawRule::Claim);
+    let shuffle = [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")];
+    let mut expected = String::new();
+
+    for i in 0..4 {
+        play(&mut game, &shuffle);
+        expected += &format!("{}. Nf3 Nf6 {}. Ng1 Ng8 ", 2 * i + 1, 2 * i + 2);
+    }
+
+    // The starting position has now come up for the fifth time
+    expected += "1/2-1/2";
+
+    let pgn = game.to_pgn();
+    let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+
+    assert!(movetext.len() > 1);
+    assert!(movetext.iter().all(|line| line.len() < 80));
+    assert_eq!(movetext.join(" "), expected);
+}
+
+#[test]
+fn dates() {
+    assert_eq!(pgn::date(UNIX_EPOCH), "1970.01.01");
+    assert_eq!(
+        pgn::date(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+        "2000.02.29"
+    );
+    assert_eq!(
+        pgn::date(UNIX_EPOCH + Duration::from_secs(1_592_697_600)),
+        "2020.06.21"
+    );
+}
+
+#[test]
+fn reading_games() {
+    let text = r#"% Exported by hand
+[Event "Casual \"blitz\""]
+[White "Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+{Paris opera} 1. e4 e5 2. Nf3 d6 3. d4 Bg4?! (3... exd4 4. Qxd4 $1) 4. dxe5 Bxf3
+5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 ; double attack
+Qe7 8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8
+13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+
+[Event "Unfinished"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1"]
+
+1... Kd7 (1... Kf7 2. e4 (2. Kf2) Ke6) 2. e4 *
+"#;
+
+    let games = pgn::read(text).unwrap();
+    assert_eq!(games.len(), 2);
+
+    let opera = &games[0];
+    assert_eq!(opera.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(opera.result, "1-0");
+    assert_eq!(opera.main_line.comments, ["Paris opera"]);
+    assert_eq!(opera.main_line.moves.len(), 33);
+
+    let bg4 = &opera.main_line.moves[5];
+    assert_eq!(bg4.mv.to_string(), "c8g4");
+    assert_eq!(bg4.nags, [6]);
+    assert_eq!(bg4.variations.len(), 1);
+    assert_eq!(bg4.variations[0].moves[1].nags, [1]);
+    assert_eq!(opera.main_line.moves[12].comments, ["double attack"]);
+
+    let game = opera.to_game(usize::MAX, DrawRule::Automatic);
+    assert_eq!(game.outcome(), Some(Outcome::WhiteWins));
+    assert_eq!(game.moves().len(), 33);
+
+    let ending = &games[1];
+    assert_eq!(ending.start.player(), Colour::Black);
+    assert_eq!(ending.result, "*");
+    let kf7 = &ending.main_line.moves[0].variations[0];
+    assert_eq!(kf7.moves.len(), 3);
+    assert_eq!(kf7.moves[1].variations[0].moves[0].mv.to_string(), "e1f2");
+}
+
+#[test]
+fn castling_with_zeros() {
+    let games = pgn::read("1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. 0-0 Bc5 5. d3 0-0 *").unwrap();
+    let moves = &games[0].main_line.moves;
+
+    assert_eq!(moves.len(), 10);
+    assert_eq!(moves[6].mv.to_string(), "e1g1");
+    assert_eq!(moves[9].mv.to_string(), "e8g8");
+}
+
+#[test]
+fn exported_games_read_back() {
+    let mut game = Game::from_position(Position::chess960(100), DrawRule::Automatic);
+    game.set_tag("Result", "0-1");
+
+    for _ in 0..10 {
+        let mv = game.position().legal_moves()[0];
+        game.play(&mv).unwrap();
+    }
+
+    let games = pgn::read(&game.to_pgn()).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].result, "0-1");
+    assert_eq!(games[0].start.to_fen(), game.start().to_fen());
+
+    let moves: Vec<_> = games[0].main_line.moves.iter().map(|m| m.mv).collect();
+    assert_eq!(moves, game.moves());
+    assert_eq!(
+        games[0].to_game(10, DrawRule::Automatic).to_pgn(),
+        game.to_pgn()
+    );
+
+    // Without a Result tag the result after the moves is kept
+    let games = pgn::read("1. e4 e5 1-0").unwrap();
+    let game = games[0].to_game(usize::MAX, DrawRule::Automatic);
+    assert_eq!(game.outcome(), None);
+    assert_eq!(game.tag("Result"), Some("1-0"));
+    assert!(game.to_pgn().trim_end().ends_with("1-0"));
+}
+
+#[test]
+fn reading_errors() {
+    assert_eq!(pgn::read("").unwrap().len(), 0);
+    assert_eq!(
+        error("1. e4 e5 2. Ke3 *"),
+        PgnError::Move(String::from("Ke3"))
+    );
+    assert_eq!(error("[White Morphy]"), PgnError::Tag);
+    assert_eq!(error("1. e4 {never closed"), PgnError::Unclosed);
+    assert_eq!(error("1. e4 (1. d4 *"), PgnError::Unclosed);
+    assert_eq!(error("(1. d4) 1. e4 *"), PgnError::Variation);
+    assert!(matches!(
+        error("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n*"),
+        PgnError::Fen(_)
+    ));
+}
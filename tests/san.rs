@@ -0,0 +1,121 @@
+use chess_rs::{MoveKind, ParseSanError, PieceKind, Position, Square};
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+fn san(fen: &str, from: &str, to: &str, promote: Option<PieceKind>) -> String {
+    let pos = Position::from_fen(fen).unwrap();
+    let mv = pos.find_move(sq(from), sq(to), promote).unwrap();
+
+    pos.san(&mv)
+}
+
+fn parse(fen: &str, san: &str) -> Result<String, ParseSanError> {
+    let pos = Position::from_fen(fen).unwrap();
+
+    pos.parse_san(san).map(|mv| mv.to_string())
+}
+
+#[test]
+fn standard_algebraic_notation() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "e2", "e4", None), "e4");
+    assert_eq!(san(start, "g1", "f3", None), "Nf3");
+
+    // Two knights, two rooks on one file and three queens
+    assert_eq!(
+        san("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1", "b1", "d2", None),
+        "Nbd2"
+    );
+    assert_eq!(
+        san("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1", "a1", "a3", None),
+        "R1a3"
+    );
+    let queens = "K7/8/1k6/8/4Q2Q/8/8/7Q w - - 0 1";
+    assert_eq!(san(queens, "h4", "e1", None), "Qh4e1");
+    assert_eq!(san(queens, "e4", "e1", None), "Qee1");
+
+    assert_eq!(
+        san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5", "d6", None),
+        "exd6"
+    );
+
+    let promotion = "k7/4P3/1K6/8/8/8/8/8 w - - 0 1";
+    assert_eq!(san(promotion, "e7", "e8", Some(PieceKind::Queen)), "e8=Q#");
+    assert_eq!(san(promotion, "e7", "e8", Some(PieceKind::Knight)), "e8=N");
+
+    let castling = "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1";
+    assert_eq!(san(castling, "e1", "g1", None), "O-O");
+    let castling = "r3k3/8/8/8/8/8/8/4K2R b Kq - 0 1";
+    assert_eq!(san(castling, "e8", "c8", None), "O-O-O");
+    assert_eq!(
+        san("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1", "g1", None),
+        "O-O+"
+    );
+}
+
+#[test]
+fn tolerant_parsing() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    for san in &["Nf3", "Ng1f3", "Ng1-f3", "g1f3", "nf3", "Nf3!?", "Nf3+"] {
+        assert_eq!(parse(start, san).as_deref(), Ok("g1f3"), "{}", san);
+    }
+    assert_eq!(parse(start, "e4").as_deref(), Ok("e2e4"));
+    assert_eq!(parse(start, "e2-e4").as_deref(), Ok("e2e4"));
+
+    let knights = "r3k2r/8/8/8/8/5N2/8/RN2K2R w KQkq - 0 1";
+    assert_eq!(parse(knights, "Nbd2").as_deref(), Ok("b1d2"));
+    assert_eq!(parse(knights, "N3d2").as_deref(), Ok("f3d2"));
+    assert_eq!(parse(knights, "Nd2"), Err(ParseSanError::Ambiguous));
+
+    for san in &["O-O", "0-0", "o-o", "e1g1", "e1h1", "Ke1g1"] {
+        assert_eq!(parse(knights, san).as_deref(), Ok("e1g1"), "{}", san);
+    }
+    assert_eq!(parse(knights, "O-O-O"), Err(ParseSanError::Illegal));
+
+    let en_passant = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    for san in &["exd6", "exd6 e.p.", "ed6", "ed", "e5d6", "e5xd6"] {
+        assert_eq!(parse(en_passant, san).as_deref(), Ok("e5d6"), "{}", san);
+    }
+
+    let promotion = "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+    for san in &["a8=Q", "a8Q", "a8(Q)", "a8/Q", "a7a8q", "a8=Q+"] {
+        assert_eq!(parse(promotion, san).as_deref(), Ok("a7a8q"), "{}", san);
+    }
+    assert_eq!(parse(promotion, "axb8=N").as_deref(), Ok("a7b8n"));
+    assert_eq!(parse(promotion, "ab8b").as_deref(), Ok("a7b8b"));
+    assert_eq!(parse(promotion, "a8"), Err(ParseSanError::Ambiguous));
+}
+
+#[test]
+fn refused_moves() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(parse(start, "e5"), Err(ParseSanError::Illegal));
+    assert_eq!(parse(start, "Nf6"), Err(ParseSanError::Illegal));
+    assert_eq!(parse(start, "Bb5"), Err(ParseSanError::Illegal));
+
+    for san in &["", "x", "Zf3", "Ni9", "e", "abcdef", "Nf3f3f3"] {
+        assert_eq!(parse(start, san), Err(ParseSanError::Invalid), "{}", san);
+    }
+}
+
+#[test]
+fn san_round_trips() {
+    for fen in &[
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    ] {
+        let pos = Position::from_fen(fen).unwrap();
+
+        for mv in pos.legal_moves() {
+            let san = pos.san(&mv);
+            assert_eq!(pos.parse_san(&san), Ok(mv), "{} in {}", san, fen);
+        }
+    }
+
+    let castling = Position::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+    let mv = castling.parse_san("O-O-O").unwrap();
+    assert_eq!(mv.kind, MoveKind::CastleQueenSide);
+}
@@ -0,0 +1,53 @@
+use chess_rs::search::{self, Limit, MATE};
+use chess_rs::Position;
+
+fn best(fen: &str, depth: u32) -> (String, i32) {
+    let pos = Position::from_fen(fen).unwrap();
+    let result = search::search(&pos, Limit::Depth(depth));
+
+    (result.best_move.unwrap().to_string(), result.score)
+}
+
+#[test]
+fn finds_mates() {
+    // Back rank mate
+    let (mv, score) = best("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+    assert_eq!((mv.as_str(), score), ("a1a8", MATE - 1));
+
+    // Mate in two with a queen sacrifice, Qg6 in WAC.001
+    let pos =
+        Position::from_fen("2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1").unwrap();
+    let result = search::search(&pos, Limit::Depth(4));
+    assert_eq!(result.best_move.unwrap().to_string(), "g3g6");
+    assert_eq!(result.mate_in(), Some(2));
+
+    // Already mated
+    let pos = Position::from_fen("6k1/5ppp/8/8/8/8/5PPP/r5K1 w - - 0 1").unwrap();
+    let result = search::search(&pos, Limit::Depth(3));
+    assert_eq!((result.best_move, result.score), (None, -MATE));
+    assert_eq!(result.mate_in(), Some(0));
+}
+
+#[test]
+fn wins_material() {
+    // The queen on d5 is hanging
+    assert_eq!(best("4k3/8/8/3q4/8/2N5/8/4K3 w - - 0 1", 2).0, "c3d5");
+
+    // Taking the pawn loses the queen
+    let (mv, _) = best("4k3/1p6/2p5/8/8/8/8/2Q1K3 w - - 0 1", 3);
+    assert_ne!(mv, "c1c6");
+}
+
+#[test]
+fn scores() {
+    let start = Position::new();
+    assert_eq!(search::evaluate(&start), 0);
+
+    let black = Position::from_fen("4k3/8/8/8/8/8/8/Q3K3 b - - 0 1").unwrap();
+    assert!(search::evaluate(&black) < -800);
+
+    // Stalemate has no moves to play and scores a draw
+    let stalemate = Position::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    let result = search::search(&stalemate, Limit::Depth(3));
+    assert_eq!((result.best_move, result.score), (None, 0));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "serde")]
+
+use chess_rs::{DrawReason, DrawRule, Game, Move, Outcome, Position};
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for san in moves {
+        let mv = game.position().parse_san(san).unwrap();
+        game.play(&mv).unwrap();
+    }
+}
+
+#[test]
+fn positions_and_moves() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 3 19";
+    let position = Position::from_fen(fen).unwrap();
+
+    let json = serde_json::to_string(&position).unwrap();
+    assert_eq!(json, format!("\"{}\"", fen));
+    let back: Position = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.to_fen(), fen);
+    assert_eq!(back.hash(), position.hash());
+
+    assert!(serde_json::from_str::<Position>("\"8/8/8 w - - 0 1\"").is_err());
+
+    let mv = position.parse_san("Nxf7").unwrap();
+    let json = serde_json::to_string(&mv).unwrap();
+    assert_eq!(
+        json,
+        r#"{"from":"e5","to":"f7","piece":"N","captured":"p","kind":"Normal"}"#
+    );
+    assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), mv);
+
+    let promotion = Position::from_fen("8/1P6/8/8/8/8/k6K/8 w - - 0 1")
+        .unwrap()
+        .parse_san("b8=N")
+        .unwrap();
+    let json = serde_json::to_string(&promotion).unwrap();
+    assert_eq!(
+        json,
+        r#"{"from":"b7","to":"b8","piece":"P","promotion":"N","kind":"Normal"}"#
+    );
+    assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), promotion);
+
+    assert!(
+        serde_json::from_str::<Move>(r#"{"from":"e9","to":"f7","piece":"N","kind":"Normal"}"#)
+            .is_err()
+    );
+    assert!(
+        serde_json::from_str::<Move>(r#"{"from":"e5","to":"f7","piece":"x","kind":"Normal"}"#)
+            .is_err()
+    );
+}
+
+#[test]
+fn games() {
+    let mut game = Game::new(DrawRule::Automatic);
+    game.set_tag("White", "Anderssen");
+    game.set_tag("Annotator", "?");
+    play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+
+    let json = serde_json::to_string(&game).unwrap();
+    assert_eq!(
+        json,
+        concat!(
+            r#"{"start":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","#,
+            r#""moves":["f3","e5","g4","Qh4#"],"#,
+            r#""tags":[["Event","?"],["Site","?"],["Date","????.??.??"],["Round","?"],"#,
+            r#"["White","Anderssen"],["Black","?"],["TimeControl","-"],["Annotator","?"]],"#,
+            r#""draw_rule":"Automatic","outcome":"BlackWins"}"#
+        )
+    );
+
+    let back: Game = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.moves(), game.moves());
+    assert_eq!(back.tags(), game.tags());
+    assert_eq!(back.outcome(), Some(Outcome::BlackWins));
+    assert_eq!(back.position().to_fen(), game.position().to_fen());
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+    // A claimed draw isn't in the moves, so it comes from the record
+    let mut game = Game::from_position(Position::chess960(0), DrawRule::Claim);
+    play(
+        &mut game,
+        &["Nc3", "Nc6", "Nd1", "Nd8", "Nc3", "Nc6", "Nd1", "Nd8"],
+    );
+    assert!(game.claim_draw());
+
+    let json = serde_json::to_string(&game).unwrap();
+    let back: Game = serde_json::from_str(&json).unwrap();
+    assert!(back.start().is_chess960());
+    assert_eq!(back.moves(), game.moves());
+    assert_eq!(back.draw_rule(), DrawRule::Claim);
+    assert_eq!(
+        back.outcome(),
+        Some(Outcome::Draw(DrawReason::ThreefoldRepetition))
+    );
+    assert_eq!(back.position().halfmove(), 8);
+    assert_eq!(back.position().fullmove(), 5);
+}
+
+#[test]
+fn illegal_games() {
+    let json = concat!(
+        r#"{"start":"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1","#,
+        r#""moves":["e4","e4"],"tags":[],"draw_rule":"Automatic","outcome":null}"#
+    );
+    let error = serde_json::from_str::<Game>(json).err().unwrap();
+    assert!(error.to_string().starts_with("e4: "));
+}
@@ -0,0 +1,1146 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use chess_rs::search::TABLEBASE_WIN;
+use chess_rs::tablebase;
+use chess_rs::{Colour, Engine, Limit, Piece, Position, Square, Tablebases, Wdl};
+
+/* A fresh directory holding the given files, each starting with the
+ * bytes given for it.
+ */
+fn directory(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("chess-rs-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for (file, bytes) in files {
+        fs::write(dir.join(file), bytes).unwrap();
+    }
+
+    dir
+}
+
+const WDL: &[u8] = &[0x71, 0xe8, 0x23, 0x5d, 0];
+const DTZ: &[u8] = &[0xd7, 0x66, 0x0c, 0xa5, 0];
+
+fn position(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap()
+}
+
+fn square_of(position: &Position, pc: char) -> usize {
+    position
+        .pieces(Piece::from_char(pc).unwrap())
+        .trailing_zeros() as usize
+}
+
+fn chebyshev(a: usize, b: usize) -> usize {
+    (a / 8).abs_diff(b / 8).max((a % 8).abs_diff(b % 8))
+}
+
+/* The board with the given pieces on the given square indices, if it's a
+ * legal position.
+ */
+fn board(pieces: &[(char, usize)], player: Colour) -> Option<Position> {
+    let mut board = [None; 64];
+
+    for (pc, sq) in pieces {
+        if board[*sq].is_some() {
+            return None;
+        }
+        board[*sq] = Piece::from_char(*pc);
+    }
+
+    let position = Position::from_board(board, player, 0);
+    position.validate().ok().map(|_| position)
+}
+
+/* Besides the published tables at the end, these write small ones in the
+ * same format: a true win/draw/loss table for king and rook against king,
+ * and made-up values for a distance-to-zero table of the same ending and
+ * win/draw/loss tables of king and two rooks against king and of king and
+ * pawn against king, with or without a pawn, which are enough to tell
+ * whether the right value is found.
+ */
+
+/* The values the made-up king and rook against king distance-to-zero
+ * table maps its values to, for wins.
+ */
+const KRK_MAP: [u8; 4] = [3, 7, 1, 12];
+
+/* The made-up distance-to-zero value of king and rook against king with
+ * white to move.
+ */
+fn krk_dtz_value(wk: usize, wr: usize, bk: usize) -> u8 {
+    ((chebyshev(wk, bk) + chebyshev(wr, bk)) % 4) as u8
+}
+
+/* The made-up result of king and pawn against king, with `black_to_move`
+ * for the side without the pawn.
+ */
+fn kpk_value(pawn: usize, wk: usize, bk: usize, black_to_move: bool) -> u8 {
+    ((pawn / 8 + chebyshev(wk, bk) + chebyshev(pawn, bk) + black_to_move as usize) % 5) as u8
+}
+
+/* The real result of king and rook against king with black to move: a
+ * loss unless black is stalemated or can take the rook.
+ */
+fn krk_black_to_move(position: &Position) -> Wdl {
+    let moves = position.legal_moves();
+
+    if moves.iter().any(|mv| mv.is_capture())
+        || (moves.is_empty() && !position.in_check(Colour::Black))
+    {
+        Wdl::Draw
+    } else {
+        Wdl::Loss
+    }
+}
+
+/* The real result of king and two rooks against king with black to move:
+ * a loss unless black is stalemated, as taking a rook still loses.
+ */
+fn krrk_black_to_move(position: &Position) -> Wdl {
+    if position.has_legal_moves() || position.in_check(Colour::Black) {
+        Wdl::Loss
+    } else {
+        Wdl::Draw
+    }
+}
+
+/* The made-up result of king and two rooks against king with black to
+ * move, the same whichever way the board is turned.
+ */
+fn krrk_value(wk: usize, bk: usize, r1: usize, r2: usize) -> u8 {
+    ((chebyshev(wk, bk) + chebyshev(r1, bk) + chebyshev(r2, bk) + chebyshev(r1, r2)) % 5) as u8
+}
+
+/* The made-up result of king and pawn against king and pawn with white to
+ * move and white's pawn on the a-file.
+ */
+fn kpkp_value(white_pawn: usize, black_pawn: usize, wk: usize, bk: usize) -> u8 {
+    ((white_pawn / 8 + 3 * (black_pawn % 8) + chebyshev(wk, black_pawn) + bk) % 5) as u8
+}
+
+fn wdl(value: u8) -> Wdl {
+    [
+        Wdl::Loss,
+        Wdl::BlessedLoss,
+        Wdl::Draw,
+        Wdl::CursedWin,
+        Wdl::Win,
+    ][value as usize]
+}
+
+/* The index of king, rook and king with the white king in the a1-d1-d4
+ * triangle: the three squares below or on the diagonal, the first one
+ * that's off it below.
+ */
+fn krk_index(mut squares: [usize; 3]) -> usize {
+    let diagonal = |sq: usize| (sq / 8) as i32 - (sq % 8) as i32;
+
+    if let Some(first) = squares.iter().find(|sq| diagonal(**sq) != 0) {
+        if diagonal(*first) > 0 {
+            for sq in squares.iter_mut() {
+                *sq = (*sq % 8) * 8 + *sq / 8;
+            }
+        }
+    }
+
+    let [s0, s1, s2] = squares;
+    let triangle = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+    let below = |sq: usize| (0..sq).filter(|s| diagonal(*s) < 0).count();
+    let adjust1 = (s1 > s0) as usize;
+    let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+
+    if diagonal(s0) != 0 {
+        let t = triangle.iter().position(|sq| *sq == s0).unwrap();
+        (t * 63 + s1 - adjust1) * 62 + s2 - adjust2
+    } else if diagonal(s1) != 0 {
+        (6 * 63 + s0 / 8 * 28 + below(s1)) * 62 + s2 - adjust2
+    } else if diagonal(s2) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + s0 / 8 * 7 * 28 + (s1 / 8 - adjust1) * 28 + below(s2)
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + s0 / 8 * 42 + (s1 / 8 - adjust1) * 6 + s2 / 8
+            - adjust2
+    }
+}
+
+/* The 462 ways to place the kings when they lead, in the order tables
+ * number them: the white king in the a1-d1-d4 triangle and the black king
+ * not next to it, nor above the diagonal when the white king is on it,
+ * with the pairs that have both kings on the diagonal last.
+ */
+fn king_pairs() -> Vec<(usize, usize)> {
+    let diagonal = |sq: usize| (sq / 8) as i32 - (sq % 8) as i32;
+    let mut pairs = Vec::new();
+
+    for both_on_diagonal in [false, true] {
+        for wk in [1, 2, 3, 10, 11, 19, 0, 9, 18, 27] {
+            for bk in 0..64 {
+                let above = diagonal(wk) == 0 && diagonal(bk) > 0;
+                let on_diagonal = diagonal(wk) == 0 && diagonal(bk) == 0;
+
+                if chebyshev(wk, bk) > 1 && !above && on_diagonal == both_on_diagonal {
+                    pairs.push((wk, bk));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+/* The values of a table for one side to move, compressed the Syzygy way:
+ * pairs of symbols replaced by new ones a few times over, then canonical
+ * Huffman codes with the longest codes first, in blocks of 32 bytes.
+ */
+struct Compressed {
+    sizes: Vec<u8>,
+    sparse: Vec<u8>,
+    lengths: Vec<u8>,
+    data: Vec<u8>,
+}
+
+const BLOCK_BITS: u8 = 5;
+const SPAN_BITS: u8 = 6;
+
+fn single_value(value: u8) -> Compressed {
+    Compressed {
+        sizes: vec![128, value],
+        sparse: Vec::new(),
+        lengths: Vec::new(),
+        data: Vec::new(),
+    }
+}
+
+fn compress(values: &[u8], flags: u8) -> Compressed {
+    let leaves = (*values.iter().max().unwrap() as usize + 1).max(2);
+    let mut pairs: Vec<(usize, usize)> = (0..leaves).map(|value| (value, 0xfff)).collect();
+    let mut symlen = vec![0; leaves];
+    let mut symbols: Vec<usize> = values.iter().map(|value| *value as usize).collect();
+
+    for _ in 0..3 {
+        let n = pairs.len();
+        let mut counts = vec![0; n * n];
+
+        for pair in symbols.windows(2) {
+            counts[pair[0] * n + pair[1]] += 1;
+        }
+
+        let (left, right) = match (0..n * n)
+            .filter(|i| counts[*i] > 0)
+            .max_by_key(|i| (counts[*i], Reverse(*i)))
+        {
+            Some(i) => (i / n, i % n),
+            None => break,
+        };
+
+        let sym = pairs.len();
+        pairs.push((left, right));
+        symlen.push(symlen[left] + symlen[right] + 1);
+
+        let mut paired = Vec::new();
+        let mut i = 0;
+
+        while i < symbols.len() {
+            if i + 1 < symbols.len() && symbols[i] == left && symbols[i + 1] == right {
+                paired.push(sym);
+                i += 2;
+            } else {
+                paired.push(symbols[i]);
+                i += 1;
+            }
+        }
+
+        symbols = paired;
+    }
+
+    // Huffman code lengths, with every symbol counted once more so that
+    // all of them get a code
+    let mut weights = vec![1; pairs.len()];
+
+    for sym in &symbols {
+        weights[*sym] += 1;
+    }
+
+    let mut heap: BinaryHeap<_> = weights
+        .iter()
+        .enumerate()
+        .map(|(sym, weight)| Reverse((*weight, sym)))
+        .collect();
+    let mut parent = vec![usize::MAX; pairs.len()];
+
+    while heap.len() > 1 {
+        let Reverse((a, i)) = heap.pop().unwrap();
+        let Reverse((b, j)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[i] = node;
+        parent[j] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+
+    let code_len: Vec<u32> = (0..pairs.len())
+        .map(|mut node| {
+            let mut len = 0;
+            while parent[node] != usize::MAX {
+                node = parent[node];
+                len += 1;
+            }
+            len
+        })
+        .collect();
+
+    // Symbols are numbered longest code first
+    let mut order: Vec<usize> = (0..pairs.len()).collect();
+    order.sort_by_key(|sym| (Reverse(code_len[*sym]), *sym));
+
+    let mut number = vec![0; pairs.len()];
+    for (n, sym) in order.iter().enumerate() {
+        number[*sym] = n;
+    }
+
+    let max_len = code_len[order[0]];
+    let min_len = *code_len.iter().min().unwrap();
+    let longer = |len: u32| code_len.iter().filter(|l| **l > len).count();
+
+    let mut base = HashMap::new();
+    base.insert(max_len, 0);
+
+    for len in (min_len..max_len).rev() {
+        let count = longer(len) - longer(len + 1);
+        base.insert(len, (base[&(len + 1)] + count) / 2);
+    }
+
+    let code = |sym: usize| {
+        let len = code_len[sym];
+        (base[&len] + number[sym] - longer(len), len)
+    };
+
+    // Blocks of whole symbols
+    let block_bits = 8 << BLOCK_BITS;
+    let mut blocks = vec![Vec::new()];
+    let mut used = 0;
+
+    for sym in &symbols {
+        if used + code_len[*sym] > block_bits {
+            blocks.push(Vec::new());
+            used = 0;
+        }
+        blocks.last_mut().unwrap().push(*sym);
+        used += code_len[*sym];
+    }
+
+    let mut data = Vec::new();
+    let mut lengths = Vec::new();
+    let mut starts = Vec::new();
+    let mut start = 0;
+
+    for block in &blocks {
+        let mut bits: Vec<bool> = Vec::new();
+
+        for sym in block {
+            let (code, len) = code(*sym);
+            bits.extend((0..len).rev().map(|bit| (code >> bit) & 1 == 1));
+        }
+
+        bits.resize(block_bits as usize, false);
+        data.extend(
+            bits.chunks(8)
+                .map(|byte| byte.iter().fold(0, |acc, bit| (acc << 1) | *bit as u8)),
+        );
+
+        let count: usize = block.iter().map(|sym| symlen[*sym] + 1).sum();
+        lengths.extend(((count - 1) as u16).to_le_bytes());
+        starts.push(start);
+        start += count;
+    }
+
+    // Sparse index entries for values k * span + span / 2, those past the
+    // end counted on from the start of the last block
+    let span = 1 << SPAN_BITS;
+    let mut sparse = Vec::new();
+
+    for k in 0..(values.len() + span - 1) / span {
+        let idx = k * span + span / 2;
+        let block = starts.iter().rposition(|start| *start <= idx).unwrap();
+        sparse.extend((block as u32).to_le_bytes());
+        sparse.extend(((idx - starts[block]) as u16).to_le_bytes());
+    }
+
+    let mut sizes = vec![flags, BLOCK_BITS, SPAN_BITS, 0];
+    sizes.extend((blocks.len() as u32).to_le_bytes());
+    sizes.push(max_len as u8);
+    sizes.push(min_len as u8);
+
+    for len in min_len..=max_len {
+        sizes.extend((longer(len) as u16).to_le_bytes());
+    }
+
+    sizes.extend((pairs.len() as u16).to_le_bytes());
+
+    for sym in &order {
+        let (left, right) = match pairs[*sym] {
+            (value, 0xfff) => (value, 0xfff),
+            (left, right) => (number[left], number[right]),
+        };
+        sizes.push(left as u8);
+        sizes.push(((left >> 8) | ((right & 0xf) << 4)) as u8);
+        sizes.push((right >> 4) as u8);
+    }
+
+    if pairs.len() % 2 == 1 {
+        sizes.push(0);
+    }
+
+    Compressed {
+        sizes,
+        sparse,
+        lengths,
+        data,
+    }
+}
+
+/* Puts a table file together: the pieces of each file of the lead pawn,
+ * then the parts of every table for one side to move, file by file.
+ */
+fn table(
+    magic: &[u8],
+    flags: u8,
+    files: &[&[u8]],
+    tables: &[Compressed],
+    map: Option<&[u8]>,
+) -> Vec<u8> {
+    let mut bytes = magic[..4].to_vec();
+    bytes.push(flags);
+
+    for pieces in files {
+        bytes.extend(*pieces);
+    }
+
+    pad(&mut bytes, 2);
+
+    for table in tables {
+        bytes.extend(&table.sizes);
+    }
+
+    if let Some(map) = map {
+        bytes.extend(map);
+        pad(&mut bytes, 2);
+    }
+
+    for table in tables {
+        bytes.extend(&table.sparse);
+    }
+
+    for table in tables {
+        bytes.extend(&table.lengths);
+    }
+
+    for table in tables {
+        pad(&mut bytes, 64);
+        bytes.extend(&table.data);
+    }
+
+    bytes
+}
+
+/* Fills `bytes` up with zeros to a multiple of `to`.
+ */
+fn pad(bytes: &mut Vec<u8>, to: usize) {
+    let len = (bytes.len() + to - 1) / to * to;
+    bytes.resize(len, 0);
+}
+
+/* The white king in the a1-d1-d4 triangle and the rook and black king
+ * anywhere, which between them cover every index.
+ */
+fn krk_placements() -> impl Iterator<Item = [usize; 3]> {
+    [0, 1, 2, 3, 9, 10, 11, 18, 19, 27]
+        .iter()
+        .flat_map(|&wk| (0..64).flat_map(move |wr| (0..64).map(move |bk| [wk, wr, bk])))
+}
+
+fn tables() -> &'static Tablebases {
+    static TABLES: OnceLock<Tablebases> = OnceLock::new();
+
+    TABLES.get_or_init(|| {
+        // Kings and rook, listed as white king, rook, black king
+        let krk_pieces: &[u8] = &[0x00, 0x66, 0x44, 0xee];
+        let mut black_to_move = vec![None; 31332];
+        let mut dtz = vec![0; 31332];
+
+        for [wk, wr, bk] in krk_placements() {
+            let pieces = [('K', wk), ('R', wr), ('k', bk)];
+            let idx = krk_index([wk, wr, bk]);
+
+            if let Some(position) = board(&pieces, Colour::Black) {
+                // Stored values run from a loss at 0 to a win at 4
+                let value = krk_black_to_move(&position) as u8;
+                assert!(black_to_move[idx].is_none() || black_to_move[idx] == Some(value));
+                black_to_move[idx] = Some(value);
+            }
+
+            if board(&pieces, Colour::White).is_some() {
+                dtz[idx] = krk_dtz_value(wk, wr, bk);
+            }
+        }
+
+        let black_to_move: Vec<u8> = black_to_move.iter().map(|v| v.unwrap_or(0)).collect();
+        let krk_wdl = table(
+            WDL,
+            1,
+            &[krk_pieces],
+            &[single_value(4), compress(&black_to_move, 0)],
+            None,
+        );
+
+        let mut map = vec![4];
+        map.extend(KRK_MAP);
+        map.extend([0, 0, 0]);
+        let krk_dtz = table(DTZ, 0, &[krk_pieces], &[compress(&dtz, 2)], Some(&map));
+
+        // Pawn, then the kings, with the pawn on the a to d files
+        let kpk_pieces: &[u8] = &[0x00, 0x11, 0x66, 0xee];
+        let mut kpk = Vec::new();
+
+        for file in 0..4 {
+            for black_to_move in [false, true] {
+                let mut values = vec![0; 23436];
+
+                for pawn in (1..7).map(|rank| rank * 8 + file) {
+                    for wk in (0..64).filter(|sq| *sq != pawn) {
+                        for bk in (0..64).filter(|sq| *sq != pawn && *sq != wk) {
+                            let wk_idx = wk - (wk > pawn) as usize;
+                            let bk_idx = bk - (bk > pawn) as usize - (bk > wk) as usize;
+                            let idx = pawn / 8 - 1 + 6 * wk_idx + 378 * bk_idx;
+                            values[idx] = kpk_value(pawn, wk, bk, black_to_move);
+                        }
+                    }
+                }
+
+                kpk.push(compress(&values, 0));
+            }
+        }
+
+        let kpk_wdl = table(WDL, 3, &[kpk_pieces; 4], &kpk, None);
+
+        // The kings lead, as neither rook is the only one of its kind, but
+        // the rooks are numbered first
+        let krrk_pieces: &[u8] = &[0x11, 0x66, 0xee, 0x44, 0x44];
+        let mut krrk = vec![0; 462 * 1891];
+
+        for (kings, (wk, bk)) in king_pairs().into_iter().enumerate() {
+            let free: Vec<usize> = (0..64).filter(|sq| *sq != wk && *sq != bk).collect();
+
+            for j in 1..62 {
+                for i in 0..j {
+                    let rooks = i + j * (j - 1) / 2;
+                    krrk[kings * 1891 + rooks] = krrk_value(wk, bk, free[i], free[j]);
+                }
+            }
+        }
+
+        let krrk_wdl = table(
+            WDL,
+            1,
+            &[krrk_pieces],
+            &[single_value(4), compress(&krrk, 0)],
+            None,
+        );
+
+        // White's pawn leads, then black's pawn and the kings. Only the
+        // a-file has values of its own, and the table is only stored for
+        // white to move as both sides have the same pieces.
+        let kpkp_pieces: &[u8] = &[0x00, 0x11, 0x11, 0x99, 0x66, 0xee];
+        let mut kpkp = vec![0; 6 * 47 * 62 * 61];
+
+        for white_pawn in (1..7).map(|rank| rank * 8) {
+            for black_pawn in (8..56).filter(|sq| *sq != white_pawn) {
+                let taken = [white_pawn, black_pawn];
+                let below = |sq: usize| taken.iter().filter(|t| **t < sq).count();
+
+                for wk in (0..64).filter(|sq| !taken.contains(sq)) {
+                    for bk in (0..64).filter(|sq| !taken.contains(sq) && *sq != wk) {
+                        let idx = white_pawn / 8 - 1
+                            + 6 * (black_pawn - 8 - (black_pawn > white_pawn) as usize)
+                            + 6 * 47 * (wk - below(wk))
+                            + 6 * 47 * 62 * (bk - below(bk) - (bk > wk) as usize);
+                        kpkp[idx] = kpkp_value(white_pawn, black_pawn, wk, bk);
+                    }
+                }
+            }
+        }
+
+        let kpkp_wdl = table(
+            WDL,
+            2,
+            &[kpkp_pieces; 4],
+            &[
+                compress(&kpkp, 0),
+                single_value(3),
+                single_value(1),
+                single_value(0),
+            ],
+            None,
+        );
+
+        let dir = directory(
+            "syzygy",
+            &[
+                ("KRvK.rtbw", &krk_wdl),
+                ("KRvK.rtbz", &krk_dtz),
+                ("KPvK.rtbw", &kpk_wdl),
+                ("KRRvK.rtbw", &krrk_wdl),
+                ("KPvKP.rtbw", &kpkp_wdl),
+            ],
+        );
+
+        Tablebases::open(dir).unwrap()
+    })
+}
+
+#[test]
+fn names() {
+    let position = Position::from_fen("8/8/4k3/8/2r5/8/1PR5/K7 w - - 0 1").unwrap();
+    assert_eq!(tablebase::table_name(&position), "KRPvKR");
+
+    let position = Position::from_fen("8/8/4k3/8/8/8/3q4/K7 b - - 0 1").unwrap();
+    assert_eq!(tablebase::table_name(&position), "KvKQ");
+}
+
+#[test]
+fn finding_tables() {
+    let dir = directory(
+        "finding",
+        &[
+            ("KQvK.rtbw", WDL),
+            ("KQvK.rtbz", DTZ),
+            ("KRPvKR.rtbw", WDL),
+            ("README.txt", b"not a table"),
+            ("notes.rtbw", b"skipped, not a table name"),
+        ],
+    );
+    let tablebases = Tablebases::open(&dir).unwrap();
+
+    assert_eq!(tablebases.len(), 3);
+    assert_eq!(tablebases.max_pieces(), 5);
+
+    // Tables are found whichever side has the material
+    let position = Position::from_fen("8/8/4k3/8/8/8/3q4/K7 b - - 0 1").unwrap();
+    assert_eq!(tablebases.wdl_table(&position).as_deref(), Some("KQvK"));
+    assert_eq!(tablebases.dtz_table(&position).as_deref(), Some("KQvK"));
+
+    let position = Position::from_fen("8/8/4k3/8/2r5/8/1PR5/K7 w - - 0 1").unwrap();
+    assert_eq!(tablebases.wdl_table(&position).as_deref(), Some("KRPvKR"));
+    assert_eq!(tablebases.dtz_table(&position), None);
+
+    assert_eq!(tablebases.wdl_table(&Position::new()), None);
+
+    // Castling rights aren't in the tables
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(tablebase::table_name(&position), "KRvK");
+    let dir = directory("castling", &[("KRvK.rtbw", WDL)]);
+    let tablebases = Tablebases::open(&dir).unwrap();
+    assert_eq!(tablebases.wdl_table(&position), None);
+
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(tablebases.wdl_table(&position).as_deref(), Some("KRvK"));
+}
+
+#[test]
+fn errors() {
+    let dir = directory("errors", &[("KQvK.rtbw", DTZ)]);
+    assert!(Tablebases::open(&dir).is_err());
+
+    let dir = directory("short", &[("KQvK.rtbz", &[0xd7])]);
+    assert!(Tablebases::open(&dir).is_err());
+
+    assert!(Tablebases::open(dir.join("missing")).is_err());
+
+    let dir = directory("empty", &[]);
+    assert!(Tablebases::open(&dir).unwrap().is_empty());
+
+    // A table that's cut short is found but can't be probed
+    let dir = directory("cut", &[("KRvK.rtbw", WDL)]);
+    let tablebases = Tablebases::open(&dir).unwrap();
+    let position = position("k7/8/1K6/8/8/8/8/1R6 w - - 0 1");
+    assert_eq!(tablebases.wdl_table(&position).as_deref(), Some("KRvK"));
+    assert_eq!(tablebases.probe_wdl(&position), None);
+}
+
+#[test]
+fn win_draw_loss() {
+    let tablebases = tables();
+
+    assert_eq!(tablebases.len(), 5);
+    assert_eq!(tablebases.max_pieces(), 4);
+
+    let cases = [
+        ("k7/8/1K6/8/8/8/8/1R6 w - - 0 1", Some(Wdl::Win)),
+        ("k7/8/1K6/8/8/8/8/R7 b - - 0 1", Some(Wdl::Loss)),
+        // Mate, stalemate and a rook left hanging
+        ("R5k1/8/6K1/8/8/8/8/8 b - - 0 1", Some(Wdl::Loss)),
+        ("k7/8/K7/8/8/8/8/1R6 b - - 0 1", Some(Wdl::Draw)),
+        ("8/8/8/8/8/2k5/2R5/K7 b - - 0 1", Some(Wdl::Draw)),
+        // With black's pieces first
+        ("K7/8/1k6/8/8/8/8/1r6 b - - 0 1", Some(Wdl::Win)),
+        ("K7/8/1k6/8/8/8/8/r7 w - - 0 1", Some(Wdl::Loss)),
+        ("8/8/8/8/8/8/8/K1k5 w - - 0 1", Some(Wdl::Draw)),
+        ("8/8/4k3/8/8/4K3/8/R7 w - - 0 1", Some(Wdl::Win)),
+        ("8/8/4k3/8/8/4K3/8/Q7 w - - 0 1", None),
+        ("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1", None),
+    ];
+
+    for (fen, wdl) in &cases {
+        assert_eq!(tablebases.probe_wdl(&position(fen)), *wdl, "{}", fen);
+    }
+
+    // Every third square for the rook and the black king, so that the
+    // white king is mirrored every way
+    for wk in 0..64 {
+        for wr in (0..64).step_by(3) {
+            for bk in (1..64).step_by(3) {
+                if let Some(position) = board(&[('K', wk), ('R', wr), ('k', bk)], Colour::Black) {
+                    assert_eq!(
+                        tablebases.probe_wdl(&position),
+                        Some(krk_black_to_move(&position)),
+                        "{}",
+                        position.to_fen()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn pawns() {
+    let tablebases = tables();
+
+    for pawn in (8..56).step_by(3) {
+        for wk in (0..64).step_by(5) {
+            for bk in (2..64).step_by(5) {
+                for player in Colour::ALL.iter() {
+                    let pieces = [('P', pawn), ('K', wk), ('k', bk)];
+                    let position = match board(&pieces, *player) {
+                        Some(position) => position,
+                        None => continue,
+                    };
+
+                    let stored = wdl(kpk_value(pawn, wk, bk, *player == Colour::Black));
+                    let moves = position.legal_moves();
+                    let captures = moves.iter().filter(|mv| mv.is_capture()).count();
+
+                    // Taking the pawn draws, so the table only has a say
+                    // when it has something better
+                    let expected = if captures == 0 {
+                        stored
+                    } else {
+                        stored.max(Wdl::Draw)
+                    };
+
+                    assert_eq!(
+                        tablebases.probe_wdl(&position),
+                        Some(expected),
+                        "{}",
+                        position.to_fen()
+                    );
+
+                    // The same with the colours the other way round
+                    let flipped: Vec<(char, usize)> = pieces
+                        .iter()
+                        .map(|(pc, sq)| {
+                            let pc = if pc.is_ascii_uppercase() {
+                                pc.to_ascii_lowercase()
+                            } else {
+                                pc.to_ascii_uppercase()
+                            };
+                            (pc, sq ^ 56)
+                        })
+                        .collect();
+                    let position = board(&flipped, player.switch()).unwrap();
+                    assert_eq!(
+                        tablebases.probe_wdl(&position),
+                        Some(expected),
+                        "{}",
+                        position.to_fen()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn kings_leading() {
+    let tablebases = tables();
+
+    for wk in 0..64 {
+        for bk in (0..64).step_by(5) {
+            for r1 in (0..64).step_by(7) {
+                for r2 in (r1 + 1..64).step_by(4) {
+                    let pieces = [('K', wk), ('k', bk), ('R', r1), ('R', r2)];
+
+                    if let Some(position) = board(&pieces, Colour::White) {
+                        assert_eq!(tablebases.probe_wdl(&position), Some(Wdl::Win));
+                    }
+
+                    let position = match board(&pieces, Colour::Black) {
+                        Some(position) => position,
+                        None => continue,
+                    };
+
+                    if position.legal_moves().iter().all(|mv| !mv.is_capture()) {
+                        assert_eq!(
+                            tablebases.probe_wdl(&position),
+                            Some(wdl(krrk_value(wk, bk, r1, r2))),
+                            "{}",
+                            position.to_fen()
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn pawns_on_both_sides() {
+    let tablebases = tables();
+
+    for white_pawn in (1..7).flat_map(|rank| [rank * 8, rank * 8 + 7]) {
+        for black_pawn in (9..56).step_by(3) {
+            for wk in (0..64).step_by(3) {
+                for bk in (1..64).step_by(4) {
+                    let pieces = [('P', white_pawn), ('p', black_pawn), ('K', wk), ('k', bk)];
+                    let position = match board(&pieces, Colour::White) {
+                        Some(position) => position,
+                        None => continue,
+                    };
+
+                    if position.legal_moves().iter().any(|mv| mv.is_capture()) {
+                        continue;
+                    }
+
+                    // The h-file is mirrored onto the a-file
+                    let mirror = if white_pawn % 8 == 7 { 7 } else { 0 };
+                    let stored = wdl(kpkp_value(
+                        white_pawn ^ mirror,
+                        black_pawn ^ mirror,
+                        wk ^ mirror,
+                        bk ^ mirror,
+                    ));
+                    assert_eq!(
+                        tablebases.probe_wdl(&position),
+                        Some(stored),
+                        "{}",
+                        position.to_fen()
+                    );
+
+                    // and black to move is the same with the colours swapped
+                    let flipped = [
+                        ('p', white_pawn ^ 56),
+                        ('P', black_pawn ^ 56),
+                        ('k', wk ^ 56),
+                        ('K', bk ^ 56),
+                    ];
+                    let position = board(&flipped, Colour::Black).unwrap();
+                    assert_eq!(
+                        tablebases.probe_wdl(&position),
+                        Some(stored),
+                        "{}",
+                        position.to_fen()
+                    );
+                }
+            }
+        }
+    }
+
+    // The lead pawn on the other files, where every value is the same
+    for (fen, wdl) in &[
+        ("8/8/4k3/1p6/8/1P6/8/4K3 w - - 0 1", Wdl::CursedWin),
+        ("8/8/4k3/6p1/8/2P5/8/4K3 w - - 0 1", Wdl::BlessedLoss),
+        ("8/8/4k3/p7/8/3P4/8/4K3 w - - 0 1", Wdl::Loss),
+    ] {
+        assert_eq!(tablebases.probe_wdl(&position(fen)), Some(*wdl), "{}", fen);
+    }
+}
+
+#[test]
+fn distance_to_zero() {
+    let tablebases = tables();
+    let sq = |name: &str| name.parse::<Square>().unwrap().index();
+
+    // White to move is in the table
+    for fen in [
+        "k7/8/1K6/8/8/8/8/1R6 w - - 0 1",
+        "8/8/4k3/8/8/4K3/8/R7 w - - 0 1",
+    ] {
+        let position = position(fen);
+        let value = krk_dtz_value(
+            square_of(&position, 'K'),
+            square_of(&position, 'R'),
+            square_of(&position, 'k'),
+        );
+        let expected = 2 * KRK_MAP[value as usize] as i32 + 1;
+        assert_eq!(tablebases.probe_dtz(&position), Some(expected), "{}", fen);
+    }
+
+    // Black to move is one move from it, and black holds out the longest
+    let mut position = position("8/8/4k3/8/8/4K3/8/R7 b - - 0 1");
+    let mut longest = 0;
+
+    for mv in position.legal_moves() {
+        let undo = position.make_move(&mv);
+        let bk = mv.to.index();
+        longest =
+            longest.min(-(2 * KRK_MAP[krk_dtz_value(sq("e3"), sq("a1"), bk) as usize] as i32 + 2));
+        position.unmake_move(&mv, undo);
+    }
+
+    assert_eq!(tablebases.probe_dtz(&position), Some(longest));
+
+    // Mated, drawn and not covered
+    assert_eq!(
+        tablebases.probe_dtz(&self::position("R5k1/8/6K1/8/8/8/8/8 b - - 0 1")),
+        Some(-1)
+    );
+    assert_eq!(
+        tablebases.probe_dtz(&self::position("8/8/8/8/8/2k5/2R5/K7 b - - 0 1")),
+        Some(0)
+    );
+    assert_eq!(
+        tablebases.probe_dtz(&self::position("8/8/4k3/8/8/4K3/8/Q7 w - - 0 1")),
+        None
+    );
+}
+
+#[test]
+fn engine_plays_from_the_tables() {
+    let mut engine = Engine::new();
+    engine.set_tablebases(tables().clone());
+
+    // Mate in one comes first at the root
+    let position = position("k7/8/1K6/8/8/8/8/7R w - - 0 1");
+    assert_eq!(
+        tables()
+            .best_move(&position)
+            .map(|(mv, dtz)| (mv.to_string(), dtz)),
+        Some((String::from("h1h8"), 1))
+    );
+
+    let result = engine.think(&position, Limit::Depth(3));
+    assert_eq!(result.best_move.unwrap().to_string(), "h1h8");
+    assert_eq!(
+        (result.score, result.depth, result.nodes),
+        (TABLEBASE_WIN - 1, 0, 0)
+    );
+
+    // Taking the knight goes into a won ending the search looks up, as
+    // there's no table for the moves that keep it
+    let position = self::position("7k/8/8/1n6/8/8/8/KR6 w - - 0 1");
+    assert_eq!(tables().best_move(&position), None);
+
+    let result = engine.think(&position, Limit::Depth(2));
+    assert_eq!(result.best_move.unwrap().to_string(), "b1b5");
+    assert_eq!((result.score, result.depth), (TABLEBASE_WIN - 1, 2));
+}
+
+/* The published tables for the smallest endings, copied from the Syzygy
+ * 3-4-5 piece set into tests/fixtures/syzygy: KRvK, KPvK, KRRvK and KPvKP,
+ * both .rtbw and .rtbz. They were written by the Syzygy generator rather
+ * than by `compress` above, so the tests that use them are run with
+ * `cargo test -- --ignored` once the files are there. Their values are
+ * checked against what the rules say about these endings, not against
+ * other probing code.
+ */
+fn published() -> Tablebases {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/syzygy");
+    let tablebases = Tablebases::open(&dir).unwrap();
+    assert_eq!(tablebases.len(), 8, "tables in {}", dir.display());
+    tablebases
+}
+
+/* Whether `wdl` is a win, a draw or a loss, leaving the fifty-move rule
+ * out.
+ */
+fn sign(wdl: Wdl) -> i32 {
+    wdl.cmp(&Wdl::Draw) as i32
+}
+
+/* Checks that the result of `position` is the best of the results its
+ * moves leave, which holds in every ending whatever numbering the table
+ * uses. Positions with a promotion to make lead to tables that aren't
+ * there, so they're skipped.
+ */
+fn check_against_moves(tablebases: &Tablebases, position: &Position) {
+    let wdl = tablebases.probe_wdl(position).unwrap();
+    let dtz = tablebases.probe_dtz(position).unwrap();
+    assert_eq!(dtz.signum(), sign(wdl), "{}", position.to_fen());
+
+    let moves = position.legal_moves();
+
+    if moves.iter().any(|mv| mv.promotion.is_some()) {
+        return;
+    }
+
+    let mut position = position.clone();
+    let best = moves
+        .iter()
+        .map(|mv| {
+            let undo = position.make_move(mv);
+            let wdl = tablebases.probe_wdl(&position).unwrap();
+            position.unmake_move(mv, undo);
+            -sign(wdl)
+        })
+        .max();
+
+    let expected = match best {
+        Some(best) => best,
+        None if position.in_check(position.player()) => -1,
+        None => 0,
+    };
+
+    assert_eq!(sign(wdl), expected, "{}", position.to_fen());
+}
+
+/* Plays the best moves of both sides from `fen` until a capture, a pawn
+ * move or the end of the game, which has to come within the distance to
+ * zero, give or take the ply that counting in moves can add.
+ */
+fn play_out(tablebases: &Tablebases, fen: &str) {
+    let mut position = position(fen);
+    let dtz = tablebases.probe_dtz(&position).unwrap();
+    let mut plies = 0;
+
+    loop {
+        let (mv, _) = tablebases.best_move(&position).unwrap();
+        position.make_move(&mv);
+        plies += 1;
+
+        if position.halfmove() == 0 || !position.has_legal_moves() {
+            break;
+        }
+
+        assert!(plies <= dtz.abs(), "{} took over {} plies", fen, dtz);
+    }
+
+    assert!(
+        plies <= dtz.abs() + 1,
+        "{} took {} plies, not {}",
+        fen,
+        plies,
+        dtz
+    );
+}
+
+#[test]
+#[ignore = "needs the published tables in tests/fixtures/syzygy"]
+fn published_pieces() {
+    let tablebases = published();
+
+    for wk in 0..64 {
+        for bk in (0..64).step_by(3) {
+            for r1 in (0..64).step_by(5) {
+                if let Some(position) = board(&[('K', wk), ('R', r1), ('k', bk)], Colour::Black) {
+                    let wdl = tablebases.probe_wdl(&position);
+                    assert_eq!(
+                        wdl,
+                        Some(krk_black_to_move(&position)),
+                        "{}",
+                        position.to_fen()
+                    );
+                }
+
+                if let Some(position) = board(&[('K', wk), ('R', r1), ('k', bk)], Colour::White) {
+                    assert_eq!(tablebases.probe_wdl(&position), Some(Wdl::Win));
+                    assert!(tablebases.probe_dtz(&position).unwrap() > 0);
+                }
+
+                // Two rooks of the same kind leave the kings to lead
+                for r2 in (r1 + 1..64).step_by(7) {
+                    let pieces = [('K', wk), ('k', bk), ('R', r1), ('R', r2)];
+
+                    if let Some(position) = board(&pieces, Colour::Black) {
+                        let wdl = tablebases.probe_wdl(&position);
+                        assert_eq!(
+                            wdl,
+                            Some(krrk_black_to_move(&position)),
+                            "{}",
+                            position.to_fen()
+                        );
+                    }
+
+                    if let Some(position) = board(&pieces, Colour::White) {
+                        assert_eq!(tablebases.probe_wdl(&position), Some(Wdl::Win));
+                    }
+                }
+            }
+        }
+    }
+
+    for fen in [
+        "8/8/8/4k3/8/8/8/KR6 w - - 0 1",
+        "8/8/8/4k3/8/8/8/KR6 b - - 0 1",
+        "8/8/8/3k4/8/8/8/KRR5 w - - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 b - - 0 1",
+    ] {
+        play_out(&tablebases, fen);
+    }
+}
+
+#[test]
+#[ignore = "needs the published tables in tests/fixtures/syzygy"]
+fn published_pawns() {
+    let tablebases = published();
+
+    for pawn in 8..56 {
+        for wk in (0..64).step_by(3) {
+            for bk in (1..64).step_by(2) {
+                for player in Colour::ALL.iter() {
+                    if let Some(position) = board(&[('P', pawn), ('K', wk), ('k', bk)], *player) {
+                        check_against_moves(&tablebases, &position);
+                    }
+                }
+            }
+        }
+    }
+
+    // Pawns on both sides, so that both are numbered as pawns
+    for white_pawn in (8..56).step_by(3) {
+        for black_pawn in (9..56).step_by(4) {
+            for wk in (0..64).step_by(7) {
+                for bk in (2..64).step_by(5) {
+                    let pieces = [('P', white_pawn), ('p', black_pawn), ('K', wk), ('k', bk)];
+
+                    for player in Colour::ALL.iter() {
+                        if let Some(position) = board(&pieces, *player) {
+                            check_against_moves(&tablebases, &position);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // En passant, which the tables leave out
+    for fen in [
+        "8/8/8/3pP3/8/8/k7/7K w - d6 0 1",
+        "8/8/8/8/3Pp3/8/K7/7k b - d3 0 1",
+        "7k/8/8/8/1pP5/1K6/8/8 b - c3 0 1",
+    ] {
+        check_against_moves(&tablebases, &position(fen));
+    }
+
+    for fen in [
+        "8/8/8/8/3k4/8/4P3/4K3 w - - 0 1",
+        "8/4k3/8/8/8/8/4P3/4K3 w - - 0 1",
+        "8/5k2/8/2p5/8/2P5/5K2/8 w - - 0 1",
+    ] {
+        play_out(&tablebases, fen);
+    }
+}
@@ -0,0 +1,50 @@
+use chess_rs::{Colour, Piece, PieceKind, Position, Square};
+
+#[test]
+fn square_names() {
+    let e4: Square = "e4".parse().unwrap();
+    assert_eq!((e4.file(), e4.rank()), (4, 3));
+    assert_eq!(e4.to_string(), "e4");
+
+    assert_eq!("a1".parse(), Ok(Square::from_index(0)));
+    assert_eq!("h8".parse(), Ok(Square::from_index(63)));
+
+    for name in &["", "e", "i1", "a9", "a0", "E4", "e44"] {
+        assert!(name.parse::<Square>().is_err(), "{}", name);
+    }
+
+    for index in 0..64 {
+        let sq = Square::from_index(index);
+        assert_eq!(sq.to_string().parse(), Ok(sq));
+    }
+}
+
+#[test]
+fn piece_letters() {
+    for colour in Colour::ALL.iter() {
+        for kind in PieceKind::ALL.iter() {
+            let pc = Piece::new(*colour, *kind);
+            assert_eq!(Piece::from_char(pc.to_char()), Some(pc));
+        }
+    }
+
+    assert_eq!(
+        Piece::from_char('n'),
+        Some(Piece::new(Colour::Black, PieceKind::Knight))
+    );
+    assert_eq!(Piece::from_char('-'), None);
+    assert_eq!(Piece::from_char('x'), None);
+}
+
+#[test]
+fn moves_between_squares() {
+    let pos = Position::new();
+    let sq = |name: &str| name.parse::<Square>().unwrap();
+
+    let mv = pos.find_move(sq("g1"), sq("f3"), None).unwrap();
+    assert_eq!(mv.piece, Piece::new(Colour::White, PieceKind::Knight));
+    assert_eq!(mv.to_string(), "g1f3");
+
+    assert!(pos.find_move(sq("e7"), sq("e5"), None).is_none());
+    assert!(pos.piece_moves(sq("e4")).is_empty());
+}
@@ -0,0 +1,117 @@
+use chess_rs::{Colour, FenError, InvalidPosition, Piece, Position, Square};
+
+fn sq(name: &str) -> Square {
+    name.parse().unwrap()
+}
+
+/* Builds the board square by square, since `from_fen` turns down the
+ * positions these tests are after and checks castling rights on its own.
+ */
+fn position(pieces: &[(char, &str)], player: Colour, castling: u8) -> Position {
+    let mut board = [None; 64];
+
+    for (pc, name) in pieces {
+        board[sq(name).index()] = Piece::from_char(*pc);
+    }
+
+    Position::from_board(board, player, castling)
+}
+
+#[test]
+fn starting_positions_are_valid() {
+    assert_eq!(Position::new().validate(), Ok(()));
+
+    for index in 0..960 {
+        assert_eq!(Position::chess960(index).validate(), Ok(()));
+    }
+}
+
+#[test]
+fn kings_and_pawns() {
+    let pos = position(
+        &[('K', "e1"), ('K', "d1"), ('P', "a8"), ('p', "h1")],
+        Colour::White,
+        0,
+    );
+
+    assert_eq!(
+        pos.validate(),
+        Err(vec![
+            InvalidPosition::KingCount(Colour::White, 2),
+            InvalidPosition::KingCount(Colour::Black, 0),
+            InvalidPosition::PawnOnBackRank(sq("h1")),
+            InvalidPosition::PawnOnBackRank(sq("a8")),
+        ])
+    );
+}
+
+#[test]
+fn side_not_to_move_in_check() {
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1").err(),
+        Some(FenError::Invalid(vec![InvalidPosition::OpponentInCheck]))
+    );
+
+    let pos = Position::from_fen("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1").unwrap();
+    assert_eq!(pos.validate(), Ok(()));
+}
+
+#[test]
+fn castling_needs_king_and_rook() {
+    let pos = position(
+        &[('K', "e1"), ('R', "h1"), ('k', "e8"), ('r', "a8")],
+        Colour::White,
+        0b1110,
+    );
+
+    assert_eq!(
+        pos.validate(),
+        Err(vec![InvalidPosition::CastlingRights(Colour::White)])
+    );
+
+    let pos = position(
+        &[('K', "e1"), ('k', "d8"), ('r', "a8")],
+        Colour::White,
+        0b0010,
+    );
+
+    assert_eq!(
+        pos.validate(),
+        Err(vec![InvalidPosition::CastlingRights(Colour::Black)])
+    );
+}
+
+#[test]
+fn too_many_pieces() {
+    let mut pieces = vec![('K', "h1"), ('k', "f8")];
+    let names: Vec<String> = (0..8)
+        .map(|i| format!("{}{}", (b'a' + i % 4) as char, 1 + i / 4))
+        .collect();
+
+    for name in &names {
+        pieces.push(('Q', name));
+    }
+
+    // Eight promoted queens and the one from the start
+    pieces.push(('Q', "e2"));
+
+    let pos = position(&pieces, Colour::White, 0);
+    assert_eq!(pos.validate(), Ok(()));
+
+    pieces.push(('P', "h2"));
+    let pos = position(&pieces, Colour::White, 0);
+    assert_eq!(
+        pos.validate(),
+        Err(vec![InvalidPosition::TooManyPieces(Colour::White)])
+    );
+}
+
+#[test]
+fn en_passant_after_a_double_step() {
+    let mut pos = Position::new();
+    let mv = pos.find_move(sq("e2"), sq("e4"), None).unwrap();
+    pos.make_move(&mv);
+
+    assert_eq!(pos.enp_w(), 0x80 >> 4);
+    assert_eq!(pos.validate(), Ok(()));
+}
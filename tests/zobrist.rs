@@ -0,0 +1,61 @@
+use chess_rs::Position;
+
+// From the Polyglot book format description, the positions after
+// e4 d5 e5 f5 Ke2 Kf7
+const KEYS: [(&str, u64); 7] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        0x463b96181691fc9c,
+    ),
+    (
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        0x823c9b50fd114196,
+    ),
+    (
+        "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        0x0756b94461c50fb0,
+    ),
+    (
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+        0x662fafb965db29d4,
+    ),
+    (
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        0x22a48b5a8e47ff78,
+    ),
+    (
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+        0x652a607ca3f242c1,
+    ),
+    (
+        "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+        0x00fdd303c946bdd9,
+    ),
+];
+
+#[test]
+fn polyglot_keys() {
+    for (fen, key) in KEYS.iter() {
+        assert_eq!(Position::from_fen(fen).unwrap().hash(), *key, "{}", fen);
+    }
+}
+
+#[test]
+fn incremental_keys() {
+    let mut position = Position::new();
+    let mut played = Vec::new();
+
+    for (san, (_, key)) in ["e4", "d5", "e5", "f5", "Ke2", "Kf7"]
+        .iter()
+        .zip(KEYS[1..].iter())
+    {
+        let mv = position.parse_san(san).unwrap();
+        played.push((mv, position.make_move(&mv)));
+        assert_eq!(position.hash(), *key, "{}", san);
+    }
+
+    while let Some((mv, undo)) = played.pop() {
+        position.unmake_move(&mv, undo);
+    }
+    assert_eq!(position.hash(), KEYS[0].1);
+}